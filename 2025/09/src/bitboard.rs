@@ -0,0 +1,145 @@
+use crate::grid::Coord;
+
+/// Largest grid dimension that fits in a `Bitboard`
+pub const MAX_DIM:usize = 11;
+
+/// Set of cells on a grid of up to `MAX_DIM` x `MAX_DIM`, stored as one bit
+/// per cell in row-major order. The grid dimension is not stored, so
+/// operations that depend on the layout of the bits take it as a parameter.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Bitboard(pub u128);
+impl Bitboard {
+    pub const EMPTY:Bitboard = Bitboard(0);
+    /// Return the bitboard of all cells on a `dim` x `dim` grid.
+    pub fn full(dim:usize) -> Bitboard {
+        return Bitboard((1u128 << (dim * dim)) - 1);
+    }
+    /// Return the bitboard holding the single cell at `coord`, which must be
+    /// on the grid.
+    pub fn from_coord(coord:&Coord, dim:usize) -> Bitboard {
+        debug_assert!(coord.x >= 0 && coord.y >= 0 && (coord.x as usize) < dim && (coord.y as usize) < dim);
+        return Bitboard(1u128 << (coord.y as usize * dim + coord.x as usize));
+    }
+    /// Return the bitboard of the given cells, or `None` if any are off the
+    /// grid.
+    pub fn from_coords<'a>(coords:impl IntoIterator<Item = &'a Coord>, dim:usize) -> Option<Bitboard> {
+        let mut board = Bitboard::EMPTY;
+        for coord in coords {
+            if coord.x < 0 || coord.y < 0 || coord.x as usize >= dim || coord.y as usize >= dim {
+                return None;
+            }
+            board |= Bitboard::from_coord(coord, dim);
+        }
+        return Some(board);
+    }
+    /// Return the bitboard of all cells in row `y`.
+    pub fn row(y:usize, dim:usize) -> Bitboard {
+        return Bitboard(((1u128 << dim) - 1) << (y * dim));
+    }
+    /// Return the bitboard of all cells in column `x`.
+    pub fn column(x:usize, dim:usize) -> Bitboard {
+        let mut board = Bitboard::EMPTY;
+        for y in 0..dim {
+            board.0 |= 1u128 << (y * dim + x);
+        }
+        return board;
+    }
+    /// Return this bitboard translated by (`dx`, `dy`). Cells must not be
+    /// translated off the grid or across a row boundary.
+    pub fn translate(&self, dx:usize, dy:usize, dim:usize) -> Bitboard {
+        return Bitboard(self.0 << (dy * dim + dx));
+    }
+    pub fn contains(&self, coord:&Coord, dim:usize) -> bool {
+        return coord.x >= 0 && coord.y >= 0 && (coord.x as usize) < dim && (coord.y as usize) < dim && self.intersects(&Bitboard::from_coord(coord, dim));
+    }
+    pub fn intersects(&self, other:&Bitboard) -> bool {
+        return self.0 & other.0 != 0;
+    }
+    pub fn is_empty(&self) -> bool {
+        return self.0 == 0;
+    }
+    pub fn count(&self) -> usize {
+        return self.0.count_ones() as usize;
+    }
+    /// Return the cells of this bitboard that are not in `other`.
+    pub fn without(&self, other:&Bitboard) -> Bitboard {
+        return Bitboard(self.0 & !other.0);
+    }
+    /// Return the single-cell bitboard of the lowest cell in row-major order.
+    pub fn lowest(&self) -> Option<Bitboard> {
+        if self.0 == 0 {
+            return None;
+        }
+        return Some(Bitboard(self.0 & self.0.wrapping_neg()));
+    }
+    /// Return the single-cell bitboard of the highest cell in row-major order.
+    pub fn highest(&self) -> Option<Bitboard> {
+        if self.0 == 0 {
+            return None;
+        }
+        return Some(Bitboard(1u128 << (127 - self.0.leading_zeros())));
+    }
+    /// Return the coordinates of all cells in row-major order.
+    pub fn coords(&self, dim:usize) -> Vec<Coord> {
+        let mut coords:Vec<Coord> = Vec::new();
+        let mut bits = self.0;
+        while bits != 0 {
+            let index = bits.trailing_zeros() as usize;
+            coords.push(Coord::new((index % dim) as isize, (index / dim) as isize));
+            bits &= bits - 1;
+        }
+        return coords;
+    }
+    /// Return all cells orthogonally adjacent to a cell of this bitboard.
+    pub fn neighbors(&self, dim:usize) -> Bitboard {
+        let not_first_column = Bitboard::full(dim).without(&Bitboard::column(0, dim));
+        let not_last_column = Bitboard::full(dim).without(&Bitboard::column(dim - 1, dim));
+        let east = (self.0 << 1) & not_first_column.0;
+        let west = (self.0 >> 1) & not_last_column.0;
+        let south = (self.0 << dim) & Bitboard::full(dim).0;
+        let north = self.0 >> dim;
+        return Bitboard(east | west | south | north);
+    }
+    /// Return the connected region of this bitboard containing the cells of
+    /// `seed`.
+    pub fn flood(&self, seed:&Bitboard, dim:usize) -> Bitboard {
+        let mut region = Bitboard(seed.0 & self.0);
+        loop {
+            let next = Bitboard((region.0 | region.neighbors(dim).0) & self.0);
+            if next == region {
+                return region;
+            }
+            region = next;
+        }
+    }
+    /// Returns true if all cells of the bitboard are orthogonally connected.
+    pub fn is_connected(&self, dim:usize) -> bool {
+        return match self.lowest() {
+            Some(seed) => self.flood(&seed, dim) == *self,
+            None => true
+        };
+    }
+    /// Return the upper-left cells of every fully occupied 2x2 block.
+    pub fn full_2x2_blocks(&self, dim:usize) -> Bitboard {
+        // Cells outside the last row and column
+        let corners = Bitboard((1u128 << (dim * (dim - 1))) - 1).without(&Bitboard::column(dim - 1, dim));
+        return Bitboard(self.0 & (self.0 >> 1) & (self.0 >> dim) & (self.0 >> (dim + 1)) & corners.0);
+    }
+}
+impl std::ops::BitOr for Bitboard {
+    type Output = Bitboard;
+    fn bitor(self, rhs:Bitboard) -> Bitboard {
+        return Bitboard(self.0 | rhs.0);
+    }
+}
+impl std::ops::BitAnd for Bitboard {
+    type Output = Bitboard;
+    fn bitand(self, rhs:Bitboard) -> Bitboard {
+        return Bitboard(self.0 & rhs.0);
+    }
+}
+impl std::ops::BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, rhs:Bitboard) {
+        self.0 |= rhs.0;
+    }
+}
@@ -0,0 +1,337 @@
+use crate::bitboard::{Bitboard, MAX_DIM};
+use crate::cells::cell_digits;
+use crate::grid::Coord;
+use crate::puzzle::{EdgeClue, Side};
+use crate::shape::{Placement, ShapeId, ShapeTable};
+
+/// The edges in the order of `Board::first_filled`
+const SIDES:[Side; 4] = [Side::Left, Side::Right, Side::Top, Side::Bottom];
+
+/// State of the board before a placement, restored by `Board::undo`
+#[derive(Debug, Copy, Clone)]
+struct Step {
+    occupied:Bitboard,
+    /// Cells filled by the placement that were empty before it
+    added:Bitboard,
+    components:usize,
+    first_filled:[u8; SIDES.len() * MAX_DIM],
+    /// Length of `Board::joined` before the placement
+    joined:usize,
+    /// Whether the placement covered a cell that was already filled
+    overlapped:bool
+}
+
+/// A partial or complete pentomino assignment for one hook/number
+/// combination, kept up to date as pentominos are placed and removed.
+/// Placing a pentomino updates the digit counts, first filled cells and
+/// connected regions for its cells only, and `undo` reverts it, so a depth
+/// first search pays for each placement once.
+#[derive(Debug, Clone)]
+pub struct Board<'a> {
+    pub dim:usize,
+    /// Table the placements index into
+    pub shapes:&'a ShapeTable,
+    /// Cells assigned each number by the hook/number combination, from
+    /// `grid::digit_masks`
+    pub digit_masks:&'a [Bitboard],
+    /// Pentominos in the order they were placed
+    pub pentominos:Vec<Placement>,
+    /// Cells covered by the pentominos
+    pub occupied:Bitboard,
+    /// Digit each cell holds once filled, indexed `y * dim + x`
+    cell_digits:Vec<u8>,
+    /// Index in `pentominos` of the pentomino covering each filled cell,
+    /// first placed first. Entries for empty cells are stale.
+    cell_pentominos:Vec<usize>,
+    /// Cells of the first and last columns, for finding the neighbors of a
+    /// placement
+    first_column:Bitboard,
+    last_column:Bitboard,
+    /// Number of filled cells holding each digit
+    digit_counts:Vec<usize>,
+    /// Number of pentominos of each shape
+    shape_counts:Vec<usize>,
+    /// Position along the line of the first filled cell seen from each edge,
+    /// indexed by side in `SIDES` order and then by line, or `u8::MAX` for an
+    /// empty line
+    first_filled:[u8; SIDES.len() * MAX_DIM],
+    /// Union-find forest of the pentominos, indexed like `pentominos`,
+    /// without path compression so that unions can be undone. A pentomino
+    /// joins the first region it touches, and the other regions it touches
+    /// are attached to that one.
+    parent:Vec<usize>,
+    /// Roots attached to another region, in the order they were attached
+    joined:Vec<usize>,
+    /// Number of connected regions of filled cells
+    components:usize,
+    steps:Vec<Step>
+}
+
+impl<'a> Board<'a> {
+    /// Return an empty board for a hook/number combination.
+    pub fn new(shapes:&'a ShapeTable, digit_masks:&'a [Bitboard]) -> Board<'a> {
+        let dim = shapes.dim;
+        return Board{
+            dim:dim,
+            shapes:shapes,
+            digit_masks:digit_masks,
+            pentominos:Vec::new(),
+            occupied:Bitboard::EMPTY,
+            cell_digits:cell_digits(digit_masks, dim),
+            cell_pentominos:vec![0; dim * dim],
+            first_column:Bitboard::column(0, dim),
+            last_column:Bitboard::column(dim - 1, dim),
+            digit_counts:vec![0; digit_masks.len()],
+            shape_counts:vec![0; shapes.shapes.len()],
+            first_filled:[u8::MAX; SIDES.len() * MAX_DIM],
+            parent:Vec::new(),
+            joined:Vec::new(),
+            components:0,
+            steps:Vec::new()
+        };
+    }
+    /// Return a board with the given pentominos placed.
+    pub fn with_pentominos(shapes:&'a ShapeTable, digit_masks:&'a [Bitboard], pentominos:&[Placement]) -> Board<'a> {
+        let mut board = Board::new(shapes, digit_masks);
+        for pentomino in pentominos.iter() {
+            board.place(*pentomino);
+        }
+        return board;
+    }
+    /// Place a pentomino. Cells it shares with pentominos already placed keep
+    /// their first pentomino, and the overlap is reported by `overlapped`.
+    pub fn place(&mut self, pentomino:Placement) {
+        let dim = self.dim;
+        let mask = self.shapes.mask(&pentomino);
+        let added = mask.without(&self.occupied);
+        self.steps.push(Step{occupied:self.occupied, added:added, components:self.components, first_filled:self.first_filled, joined:self.joined.len(), overlapped:mask.intersects(&self.occupied)});
+        self.occupied |= mask;
+        let shape = self.shapes.shape(&pentomino);
+        self.shape_counts[shape] += 1;
+        let index = self.pentominos.len();
+        self.pentominos.push(pentomino);
+        self.parent.push(index);
+        if !added.is_empty() {
+            self.components += 1;
+        }
+        let east = (added.0 << 1) & !self.first_column.0;
+        let west = (added.0 >> 1) & !self.last_column.0;
+        let touched = (east | west | added.0 << dim | added.0 >> dim) & self.steps.last().unwrap().occupied.0;
+        let mut bits = added.0;
+        while bits != 0 {
+            let cell = bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+            self.cell_pentominos[cell] = index;
+            self.digit_counts[self.cell_digits[cell] as usize] += 1;
+        }
+        let orientation = self.shapes.orientation(&pentomino);
+        for y in pentomino.y as usize..pentomino.y as usize + orientation.height {
+            let row = (added.0 >> (y * dim)) as u16 & ((1 << dim) - 1);
+            if row == 0 {
+                continue;
+            }
+            let (left, right) = (row.trailing_zeros() as u8, (15 - row.leading_zeros()) as u8);
+            self.first_filled[y] = self.first_filled[y].min(left);
+            self.first_filled[dim + y] = self.first_filled[dim + y].min(dim as u8 - 1 - right);
+            let mut columns = row;
+            while columns != 0 {
+                let x = columns.trailing_zeros() as usize;
+                columns &= columns - 1;
+                self.first_filled[2 * dim + x] = self.first_filled[2 * dim + x].min(y as u8);
+                self.first_filled[3 * dim + x] = self.first_filled[3 * dim + x].min((dim - 1 - y) as u8);
+            }
+        }
+        let mut region:Option<usize> = None;
+        let mut bits = touched;
+        while bits != 0 {
+            let root = self.find(self.cell_pentominos[bits.trailing_zeros() as usize]);
+            bits &= bits - 1;
+            match region {
+                None => {
+                    self.parent[index] = root;
+                    region = Some(root);
+                    self.components -= 1;
+                },
+                Some(region) if region != root => {
+                    self.parent[root] = region;
+                    self.joined.push(root);
+                    self.components -= 1;
+                },
+                _ => ()
+            }
+        }
+    }
+    /// Undo and place pentominos until the board holds `pentominos` in the
+    /// given order, keeping the placements they share with the board.
+    pub fn follow(&mut self, pentominos:&[Placement]) {
+        let common = self.pentominos.iter().zip(pentominos.iter()).take_while(|(a, b)| a == b).count();
+        while self.pentominos.len() > common {
+            self.undo();
+        }
+        for pentomino in pentominos[common..].iter() {
+            self.place(*pentomino);
+        }
+    }
+    /// Remove the pentomino placed last, restoring the board to its state
+    /// before the placement.
+    pub fn undo(&mut self) {
+        let step = match self.steps.pop() {
+            Some(step) => step,
+            None => return
+        };
+        let pentomino = self.pentominos.pop().unwrap();
+        self.shape_counts[self.shapes.shape(&pentomino)] -= 1;
+        self.parent.pop();
+        while self.joined.len() > step.joined {
+            let root = self.joined.pop().unwrap();
+            self.parent[root] = root;
+        }
+        let mut bits = step.added.0;
+        while bits != 0 {
+            let cell = bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+            self.digit_counts[self.cell_digits[cell] as usize] -= 1;
+        }
+        self.occupied = step.occupied;
+        self.components = step.components;
+        self.first_filled = step.first_filled;
+    }
+    /// Return the root of the region of a pentomino.
+    fn find(&self, pentomino:usize) -> usize {
+        let mut root = pentomino;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        return root;
+    }
+    /// Return the pentomino placed last.
+    pub fn last(&self) -> Option<&Placement> {
+        return self.pentominos.last();
+    }
+    /// Returns true if the pentomino placed last covered a filled cell.
+    pub fn overlapped(&self) -> bool {
+        return self.steps.last().is_some_and(|step| step.overlapped);
+    }
+    /// Return the cells of the board that hold digit `d`.
+    pub fn digit_cells(&self, d:usize) -> Bitboard {
+        return self.occupied & self.digit_masks[d];
+    }
+    /// Return the number of filled cells holding digit `d`.
+    pub fn digit_count(&self, d:usize) -> usize {
+        return self.digit_counts[d];
+    }
+    /// Return the digit in a cell, or `None` if it is empty.
+    pub fn digit(&self, coord:&Coord) -> Option<usize> {
+        if !self.occupied.contains(coord, self.dim) {
+            return None;
+        }
+        return Some(self.cell_digits[coord.y as usize * self.dim + coord.x as usize] as usize);
+    }
+    /// Return the shape of the pentomino covering a cell.
+    pub fn shape_at(&self, coord:&Coord) -> Option<ShapeId> {
+        if !self.occupied.contains(coord, self.dim) {
+            return None;
+        }
+        return Some(self.shapes.shape(&self.pentominos[self.cell_pentominos[coord.y as usize * self.dim + coord.x as usize]]));
+    }
+    /// Return the number of pentominos of a shape.
+    pub fn shape_count(&self, shape:ShapeId) -> usize {
+        return self.shape_counts[shape];
+    }
+    /// Return the digits the combination assigns to the given cells, whether
+    /// filled or not.
+    pub fn digits_of(&self, cells:&Bitboard) -> impl Iterator<Item = usize> + '_ {
+        let mut bits = cells.0;
+        return std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let cell = bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+            return Some(self.cell_digits[cell] as usize);
+        });
+    }
+    /// Return the sum of the digits within a pentomino.
+    pub fn sum(&self, pentomino:&Placement) -> usize {
+        let mut sum = 0;
+        let mut bits = self.shapes.mask(pentomino).0;
+        while bits != 0 {
+            sum += self.cell_digits[bits.trailing_zeros() as usize] as usize;
+            bits &= bits - 1;
+        }
+        return sum;
+    }
+    /// Return the first filled cell on the line of an edge clue, traversing
+    /// from the clue's edge.
+    pub fn first_filled(&self, clue:&EdgeClue) -> Option<Coord> {
+        let side = SIDES.iter().position(|side| *side == clue.side).unwrap();
+        let position = match self.first_filled[side * self.dim + clue.line] {
+            u8::MAX => return None,
+            position => position as isize
+        };
+        let (line, last) = (clue.line as isize, self.dim as isize - 1);
+        return Some(match clue.side {
+            Side::Left => Coord::new(position, line),
+            Side::Right => Coord::new(last - position, line),
+            Side::Top => Coord::new(line, position),
+            Side::Bottom => Coord::new(line, last - position)
+        });
+    }
+    /// Return the number of connected regions of filled cells.
+    pub fn components(&self) -> usize {
+        return self.components;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Board;
+    use crate::bitboard::Bitboard;
+    use crate::polyomino::DEFAULT_PIECE_SIZE;
+    use crate::shape::{Placement, ShapeTable};
+
+    /// Return the state `undo` restores, leaving out the stale entries of
+    /// `cell_pentominos`.
+    fn state(board:&Board) -> String {
+        return format!("{:?} {:?} {:?} {:?} {:?} {:?} {:?} {}", board.pentominos, board.occupied, board.digit_counts, board.shape_counts, board.first_filled, board.parent, board.joined, board.components);
+    }
+
+    #[test]
+    fn undo_restores_masks_and_regions() {
+        let dim = 5;
+        let shapes = ShapeTable::get(dim, DEFAULT_PIECE_SIZE);
+        // Each row holds its own digit
+        let digit_masks:Vec<Bitboard> = (0..=dim).map(|d| if d == 0 { Bitboard::EMPTY } else { Bitboard::row(d - 1, dim) }).collect();
+        let placements:Vec<Placement> = (0..shapes.shapes.len()).flat_map(|shape| shapes.placements(shape)).collect();
+        // Two separate pentominos, and a third that joins their regions
+        let a = placements[0];
+        let mask_a = shapes.mask(&a);
+        let b = *placements.iter().find(|p| !shapes.mask(p).intersects(&(mask_a | mask_a.neighbors(dim)))).unwrap();
+        let mask_b = shapes.mask(&b);
+        let c = *placements.iter().find(|p| {
+            let mask = shapes.mask(p);
+            return !mask.intersects(&(mask_a | mask_b)) && mask.intersects(&mask_a.neighbors(dim)) && mask.intersects(&mask_b.neighbors(dim));
+        }).unwrap();
+        let mut board = Board::new(shapes, &digit_masks);
+        let mut states = vec![state(&board)];
+        for pentomino in [a, b] {
+            board.place(pentomino);
+            states.push(state(&board));
+        }
+        assert_eq!(board.components(), 2);
+        board.place(c);
+        states.push(state(&board));
+        assert_eq!(board.components(), 1);
+        assert_eq!(board.occupied, mask_a | mask_b | shapes.mask(&c));
+        // An overlapping placement is undone as well
+        board.place(a);
+        assert!(board.overlapped());
+        board.undo();
+        while let Some(expected) = states.pop() {
+            assert_eq!(state(&board), expected);
+            board.undo();
+        }
+        assert!(board.pentominos.is_empty());
+        assert_eq!(board.digit_counts, vec![0; dim + 1]);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::bitboard::Bitboard;
+use crate::constraint::Constraint;
+use crate::stats::Rule;
+use std::sync::Arc;
+
+/// Cells of a hook/number combination decided so far by the cell search.
+/// The combination gives each hook its number, so a cell is either empty or
+/// holds the number of its hook, and every cell is filled, empty or still
+/// open.
+#[derive(Debug, Copy, Clone)]
+pub struct Cells<'a> {
+    pub dim:usize,
+    /// Cells assigned each number by the combination, from
+    /// `grid::digit_masks`
+    pub digit_masks:&'a [Bitboard],
+    /// Digit each cell holds if filled, from `cell_digits`
+    cell_digits:&'a [u8],
+    /// Cells of each placement that passes the puzzle's checks on its own
+    pub placements:&'a [Bitboard],
+    pub filled:Bitboard,
+    pub empty:Bitboard
+}
+
+impl<'a> Cells<'a> {
+    /// Return cells with none decided.
+    pub fn new(dim:usize, digit_masks:&'a [Bitboard], cell_digits:&'a [u8], placements:&'a [Bitboard]) -> Cells<'a> {
+        return Cells{dim:dim, digit_masks:digit_masks, cell_digits:cell_digits, placements:placements, filled:Bitboard::EMPTY, empty:Bitboard::EMPTY};
+    }
+    /// Return the cells that are neither filled nor empty yet.
+    pub fn open(&self) -> Bitboard {
+        return Bitboard::full(self.dim).without(&(self.filled | self.empty));
+    }
+    /// Fill open cells.
+    pub fn fill(&mut self, cells:Bitboard) {
+        debug_assert!(!cells.intersects(&self.empty));
+        self.filled |= cells;
+    }
+    /// Leave open cells empty.
+    pub fn clear(&mut self, cells:Bitboard) {
+        debug_assert!(!cells.intersects(&self.filled));
+        self.empty |= cells;
+    }
+    /// Return the given cells whose digit satisfies `keep`.
+    pub fn with_digits(&self, cells:&Bitboard, keep:impl Fn(usize) -> bool) -> Bitboard {
+        let mut kept = Bitboard::EMPTY;
+        let mut bits = cells.0;
+        while bits != 0 {
+            let cell = bits & bits.wrapping_neg();
+            bits &= bits - 1;
+            if keep(self.cell_digits[cell.trailing_zeros() as usize] as usize) {
+                kept.0 |= cell;
+            }
+        }
+        return kept;
+    }
+    /// Return the digits the combination assigns to the given cells, whether
+    /// filled or not.
+    pub fn digits_of(&self, cells:&Bitboard) -> impl Iterator<Item = usize> + '_ {
+        let mut bits = cells.0;
+        return std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let cell = bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+            return Some(self.cell_digits[cell] as usize);
+        });
+    }
+}
+
+/// Return the digit each cell is assigned by a hook/number combination,
+/// indexed `y * dim + x`.
+pub fn cell_digits(digit_masks:&[Bitboard], dim:usize) -> Vec<u8> {
+    let mut digits = vec![0; dim * dim];
+    for (d, mask) in digit_masks.iter().enumerate() {
+        for coord in mask.coords(dim) {
+            digits[coord.y as usize * dim + coord.x as usize] = d as u8;
+        }
+    }
+    return digits;
+}
+
+/// Run the propagators of the constraints until none of them decides another
+/// cell, returning the rule of the first one that finds the cells cannot be
+/// completed.
+pub fn propagate(constraints:&[Arc<dyn Constraint>], cells:&mut Cells) -> Result<(), Rule> {
+    loop {
+        let decided = (cells.filled, cells.empty);
+        for constraint in constraints.iter() {
+            constraint.propagate(cells)?;
+        }
+        if (cells.filled, cells.empty) == decided {
+            return Ok(());
+        }
+    }
+}
+
+/// Return the open cell to branch on, or `None` if every cell is decided.
+/// The cell is taken from the hook with the fewest open cells, next to a
+/// filled cell if it has one.
+pub fn branch_cell(cells:&Cells) -> Option<Bitboard> {
+    let open = cells.open();
+    let hook = cells.digit_masks.iter().map(|mask| *mask & open).filter(|cells| !cells.is_empty()).min_by_key(|cells| cells.count())?;
+    let touching = hook & cells.filled.neighbors(cells.dim);
+    return touching.lowest().or(hook.lowest());
+}
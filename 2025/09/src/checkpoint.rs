@@ -0,0 +1,272 @@
+use std::collections::{BTreeMap, BTreeSet};
+use crate::grid::{hook_layout_hash, Hook};
+use crate::json::SolutionJson;
+use crate::puzzle::Puzzle;
+use crate::shape::{Placement, ShapeId, ShapeTable};
+use crate::HooksSolution;
+use serde::{Deserialize, Serialize};
+
+/// Progress of a pentomino search over a list of hook/number combinations:
+/// the combinations and the prefix subtrees of each combination that have
+/// been searched to completion, and the solutions found so far.
+#[derive(Debug, Clone, Default)]
+pub struct Checkpoint {
+    /// Number of hook/number combinations in the search
+    pub combinations:usize,
+    pub completed_combinations:BTreeSet<usize>,
+    /// Keys of the completed prefixes of combinations that are not yet
+    /// complete, from `prefix_key`
+    pub completed_prefixes:BTreeMap<usize, BTreeSet<String>>,
+    /// Solutions found with the index of their combination
+    pub solutions:Vec<(usize, Vec<Placement>)>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CheckpointJson {
+    dim:usize,
+    /// Description of the puzzle the checkpoint was written for, as written
+    /// by `Puzzle`'s `Display`
+    puzzle:String,
+    combinations:usize,
+    /// Hash of the combination list from `combinations_hash`, in hexadecimal
+    combinations_hash:String,
+    completed_combinations:Vec<usize>,
+    completed_prefixes:BTreeMap<usize, Vec<String>>,
+    solutions:Vec<CheckpointSolutionJson>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CheckpointSolutionJson {
+    combination:usize,
+    solution:SolutionJson
+}
+
+/// Return a key identifying a partial pentomino assignment, listing each
+/// pentomino's class, position and orientation rows followed by the classes
+/// left out of the assignment, such as `I@1,0:##### U@6,0:#.#/### -F`.
+pub fn prefix_key(shapes:&ShapeTable, pentominos:&[Placement], excluded:&[ShapeId]) -> String {
+    let mut keys:Vec<String> = pentominos.iter().map(|p| format!("{}@{},{}:{}", shapes.class(p), p.x, p.y, shapes.orientation(p).rows.join("/"))).collect();
+    keys.extend(excluded.iter().map(|class| format!("-{}", shapes.name(*class))));
+    return keys.join(" ");
+}
+
+/// Return a hash identifying an ordered list of hook/number combinations, the
+/// same across runs and platforms: 64-bit FNV-1a over the hook layout hash
+/// and the numbers of each combination in order.
+fn combinations_hash(hook_number_assignments:&[(Vec<Hook>, Vec<usize>)]) -> u64 {
+    let mut hash:u64 = 0xcbf29ce484222325;
+    for (hooks, assignment) in hook_number_assignments.iter() {
+        for value in std::iter::once(hook_layout_hash(hooks)).chain(assignment.iter().map(|n| *n as u64)) {
+            for byte in value.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+    }
+    return hash;
+}
+
+impl Checkpoint {
+    pub fn new(combinations:usize) -> Checkpoint {
+        return Checkpoint{combinations:combinations, ..Default::default()};
+    }
+    /// Returns true if the prefix of a combination has been searched to
+    /// completion.
+    pub fn prefix_completed(&self, combination:usize, key:&str) -> bool {
+        return self.completed_combinations.contains(&combination) || self.completed_prefixes.get(&combination).is_some_and(|keys| keys.contains(key));
+    }
+    /// Record a prefix of a combination as searched to completion.
+    pub fn complete_prefix(&mut self, combination:usize, key:String) {
+        self.completed_prefixes.entry(combination).or_default().insert(key);
+    }
+    /// Record a combination as searched to completion, dropping its completed
+    /// prefixes.
+    pub fn complete_combination(&mut self, combination:usize) {
+        self.completed_prefixes.remove(&combination);
+        self.completed_combinations.insert(combination);
+    }
+    /// Record a solution, unless it was already recorded before the search
+    /// was resumed.
+    pub fn add_solution(&mut self, combination:usize, pentominos:&[Placement]) {
+        if !self.solutions.iter().any(|(c, p)| *c == combination && p == pentominos) {
+            self.solutions.push((combination, pentominos.to_vec()));
+        }
+    }
+    /// Write the checkpoint to a file, replacing it only once the new
+    /// checkpoint has been written in full.
+    pub fn write(&self, path:&str, puzzle:&Puzzle, hook_number_assignments:&[(Vec<Hook>, Vec<usize>)]) -> Result<(), String> {
+        let solutions = self.solutions.iter().map(|(combination, pentominos)| {
+            let (hooks, assignment) = &hook_number_assignments[*combination];
+            let solution = HooksSolution::new(puzzle, hooks, assignment, pentominos);
+            CheckpointSolutionJson{combination:*combination, solution:SolutionJson::new(&solution)}
+        }).collect();
+        let json = CheckpointJson{
+            dim:puzzle.dim,
+            puzzle:puzzle.to_string(),
+            combinations:self.combinations,
+            combinations_hash:format!("{:016x}", combinations_hash(hook_number_assignments)),
+            completed_combinations:self.completed_combinations.iter().copied().collect(),
+            completed_prefixes:self.completed_prefixes.iter().map(|(c, keys)| (*c, keys.iter().cloned().collect())).collect(),
+            solutions:solutions
+        };
+        let temp_path = format!("{}.tmp", path);
+        if let Err(e) = std::fs::write(&temp_path, serde_json::to_string_pretty(&json).unwrap()) {
+            return Err(format!("Could not write checkpoint file '{}': {}", temp_path, e));
+        }
+        if let Err(e) = std::fs::rename(&temp_path, path) {
+            return Err(format!("Could not replace checkpoint file '{}': {}", path, e));
+        }
+        return Ok(());
+    }
+    /// Read a checkpoint written for the same puzzle and combinations,
+    /// refusing one written for another puzzle or combination list and
+    /// verifying the combinations and solutions it records.
+    pub fn from_file(path:&str, puzzle:&Puzzle, hook_number_assignments:&[(Vec<Hook>, Vec<usize>)]) -> Result<Checkpoint, String> {
+        let input = match std::fs::read_to_string(path) {
+            Ok(input) => input,
+            Err(e) => return Err(format!("Could not read checkpoint file '{}': {}", path, e))
+        };
+        let json:CheckpointJson = match serde_json::from_str(&input) {
+            Ok(json) => json,
+            Err(e) => return Err(format!("Could not parse checkpoint file '{}': {}", path, e))
+        };
+        if json.dim != puzzle.dim || json.combinations != hook_number_assignments.len() {
+            return Err(format!("Checkpoint file '{}' is for a {}x{} puzzle with {} combinations, not this {}x{} puzzle with {} combinations", path, json.dim, json.dim, json.combinations, puzzle.dim, puzzle.dim, hook_number_assignments.len()));
+        }
+        if json.puzzle != puzzle.to_string() {
+            return Err(format!("Checkpoint file '{}' was written for a different puzzle:\n{}", path, json.puzzle));
+        }
+        if json.combinations_hash != format!("{:016x}", combinations_hash(hook_number_assignments)) {
+            return Err(format!("Checkpoint file '{}' was written for a different list of hook/number combinations", path));
+        }
+        let mut checkpoint = Checkpoint::new(json.combinations);
+        for combination in json.completed_combinations {
+            if combination >= json.combinations {
+                return Err(format!("Checkpoint file '{}' records combination {} as complete, but there are only {} combinations", path, combination, json.combinations));
+            }
+            checkpoint.complete_combination(combination);
+        }
+        for (combination, keys) in json.completed_prefixes {
+            if combination >= json.combinations {
+                return Err(format!("Checkpoint file '{}' records prefixes of combination {} as complete, but there are only {} combinations", path, combination, json.combinations));
+            }
+            for key in keys {
+                checkpoint.complete_prefix(combination, key);
+            }
+        }
+        for recorded in json.solutions {
+            let solution = match recorded.solution.to_solution(puzzle) {
+                Ok(solution) => solution,
+                Err(e) => return Err(format!("Checkpoint file '{}' has an invalid solution: {}", path, e))
+            };
+            match hook_number_assignments.get(recorded.combination) {
+                Some((hooks, assignment)) if *hooks == solution.hooks && *assignment == solution.assignment => {
+                    checkpoint.add_solution(recorded.combination, &solution.pentominos);
+                },
+                _ => return Err(format!("Checkpoint file '{}' has a solution that does not match combination {}", path, recorded.combination))
+            }
+        }
+        return Ok(checkpoint);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Checkpoint;
+    use crate::grid::{Hook, SearchOptions};
+    use crate::puzzle::Puzzle;
+
+    /// 5x5 puzzle with only pentomino clues, which leave the hooks and
+    /// numbers allowed on the empty 5x5 puzzle unchanged
+    const CLUED_5X5:&str = "
+. . . . . . .
+. . . . . . .
+U . . . . . .
+. . . . . . .
+. . . . . . .
+. . . . . . .
+. . . N . . .
+";
+    const EMPTY_5X5:&str = "
+. . . . . . .
+. . . . . . .
+. . . . . . .
+. . . . . . .
+. . . . . . .
+. . . . . . .
+. . . . . . .
+";
+
+    fn combinations(puzzle:&Puzzle) -> Vec<(Vec<Hook>, Vec<usize>)> {
+        return crate::enumerate_number_assignments(puzzle, &crate::enumerate_hook_arrangements(puzzle));
+    }
+
+    /// Return a checkpoint path in the temporary directory unique to the
+    /// test.
+    fn temp_path(name:&str) -> String {
+        return std::env::temp_dir().join(format!("sept2025-{}-{}.json", std::process::id(), name)).to_string_lossy().into_owned();
+    }
+
+    /// Return the solutions of a search as their combination index and
+    /// pentominos, in a fixed order.
+    fn solve(puzzle:&Puzzle, combinations:&[(Vec<Hook>, Vec<usize>)], options:&SearchOptions) -> Vec<String> {
+        let mut found:Vec<String> = crate::solve_pentominos(puzzle, combinations, options, &|_, _| {}).unwrap().iter().map(|solution| {
+            let index = combinations.iter().position(|(hooks, assignment)| *hooks == solution.hooks && *assignment == solution.assignment).unwrap();
+            let mut pentominos = solution.pentominos.clone();
+            pentominos.sort();
+            return format!("{} {:?}", index, pentominos);
+        }).collect();
+        found.sort();
+        return found;
+    }
+
+    #[test]
+    fn round_trips_through_a_file() {
+        let puzzle = Puzzle::parse(CLUED_5X5).unwrap();
+        let combinations = combinations(&puzzle);
+        let solution = &crate::solve_pentominos(&puzzle, &combinations, &SearchOptions{threads:2, ..Default::default()}, &|_, _| {}).unwrap()[0];
+        let index = combinations.iter().position(|(hooks, assignment)| *hooks == solution.hooks && *assignment == solution.assignment).unwrap();
+        let mut checkpoint = Checkpoint::new(combinations.len());
+        checkpoint.complete_combination(index);
+        checkpoint.complete_prefix(index + 1, "I@0,0:##### -F".to_string());
+        checkpoint.add_solution(index, &solution.pentominos);
+        let path = temp_path("round-trip");
+        checkpoint.write(&path, &puzzle, &combinations).unwrap();
+        let read = Checkpoint::from_file(&path, &puzzle, &combinations);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(format!("{:?}", read.unwrap()), format!("{:?}", checkpoint));
+    }
+
+    #[test]
+    fn resumed_search_finds_every_solution() {
+        let puzzle = Puzzle::parse(CLUED_5X5).unwrap();
+        let combinations = combinations(&puzzle);
+        let uninterrupted = solve(&puzzle, &combinations, &SearchOptions{threads:2, all:true, ..Default::default()});
+        // Stop the first run early, leaving some combinations and prefixes
+        // unsearched
+        let path = temp_path("resume");
+        let interrupted = solve(&puzzle, &combinations, &SearchOptions{threads:2, all:true, max_solutions:Some(20), checkpoint_path:Some(path.clone()), ..Default::default()});
+        assert_eq!(interrupted.len(), 20);
+        let checkpoint = Checkpoint::from_file(&path, &puzzle, &combinations);
+        std::fs::remove_file(&path).unwrap();
+        let checkpoint = checkpoint.unwrap();
+        assert!(checkpoint.completed_combinations.len() < combinations.len());
+        let resumed = solve(&puzzle, &combinations, &SearchOptions{threads:2, all:true, resume:Some(checkpoint), ..Default::default()});
+        assert_eq!(resumed, uninterrupted);
+    }
+
+    #[test]
+    fn rejects_a_checkpoint_from_another_puzzle() {
+        let clued = Puzzle::parse(CLUED_5X5).unwrap();
+        let empty = Puzzle::parse(EMPTY_5X5).unwrap();
+        let combinations = combinations(&clued);
+        assert_eq!(combinations, self::combinations(&empty));
+        let mut checkpoint = Checkpoint::new(combinations.len());
+        checkpoint.complete_combination(0);
+        let path = temp_path("other-puzzle");
+        checkpoint.write(&path, &clued, &combinations).unwrap();
+        let read = Checkpoint::from_file(&path, &empty, &combinations);
+        std::fs::remove_file(&path).unwrap();
+        assert!(read.unwrap_err().contains("different puzzle"));
+    }
+}
@@ -0,0 +1,486 @@
+use crate::bitboard::Bitboard;
+use crate::board::Board;
+use crate::cells::Cells;
+use crate::grid::{clue_first_cell, clue_line_mask, Coord};
+use crate::polyomino::Polyomino;
+use crate::puzzle::{ClueValue, EdgeClue, Side};
+use crate::stats::Rule;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// Modulus the digits within each piece must sum to a multiple of in Hooks 11
+pub const SUM_MODULUS:usize = 5;
+
+/// A rule of a Hooks puzzle on the placement of pentominos. A puzzle is
+/// checked against a list of constraints, so variants with different rules
+/// can be solved by giving them a different list.
+pub trait Constraint: Debug + Send + Sync {
+    /// Returns false if no placement of the piece shape can ever satisfy the
+    /// constraint, so the shape is left out of the search.
+    fn admits_shape(&self, _shape:&Polyomino) -> bool {
+        return true;
+    }
+    /// Check an assignment that may still be extended, returning the rule
+    /// broken. An assignment that has a valid completion must be accepted.
+    /// The check is made after each placement, once the assignment without
+    /// its last pentomino has passed, so only what the last placement changes
+    /// needs checking.
+    fn check_partial(&self, _board:&Board) -> Result<(), Rule> {
+        return Ok(());
+    }
+    /// Check a complete assignment that has passed `check_partial`.
+    fn check_complete(&self, _board:&Board) -> Result<(), Rule> {
+        return Ok(());
+    }
+    /// Fill or empty the open cells of the cell search that every completion
+    /// of the cells must fill or leave empty, returning the rule broken if
+    /// there is no completion. Rules on the pentominos themselves are left to
+    /// the division of complete fillings, which is checked with
+    /// `check_partial` and `check_complete`.
+    fn propagate(&self, _cells:&mut Cells) -> Result<(), Rule> {
+        return Ok(());
+    }
+}
+
+/// Pentominos do not overlap. Placements always lie within the grid.
+#[derive(Debug)]
+pub struct NoOverlap;
+impl Constraint for NoOverlap {
+    fn check_partial(&self, board:&Board) -> Result<(), Rule> {
+        if board.overlapped() {
+            return Err(Rule::Overlap);
+        }
+        return Ok(());
+    }
+}
+
+/// No two pentominos are of the same class.
+#[derive(Debug)]
+pub struct DistinctClasses;
+impl Constraint for DistinctClasses {
+    fn check_partial(&self, board:&Board) -> Result<(), Rule> {
+        if board.last().is_some_and(|p| board.shape_count(board.shapes.shape(p)) > 1) {
+            return Err(Rule::RepeatedClass);
+        }
+        return Ok(());
+    }
+}
+
+/// The digits within each pentomino sum to a multiple of `SUM_MODULUS`. The
+/// modulus is fixed: the verifier and the SAT encoding apply the same one.
+#[derive(Debug)]
+pub struct SumModulus;
+impl Constraint for SumModulus {
+    fn check_partial(&self, board:&Board) -> Result<(), Rule> {
+        if board.last().is_some_and(|p| !board.sum(p).is_multiple_of(SUM_MODULUS)) {
+            return Err(Rule::SumModulus);
+        }
+        return Ok(());
+    }
+}
+
+/// Each digit d appears d times: never more in a partial assignment, and
+/// exactly d times in a complete one.
+#[derive(Debug)]
+pub struct DigitCounts;
+impl Constraint for DigitCounts {
+    fn check_partial(&self, board:&Board) -> Result<(), Rule> {
+        if (0..board.digit_masks.len()).any(|d| board.digit_count(d) > d) {
+            return Err(Rule::DigitOvercount);
+        }
+        return Ok(());
+    }
+    fn check_complete(&self, board:&Board) -> Result<(), Rule> {
+        if (0..board.digit_masks.len()).any(|d| board.digit_count(d) != d) {
+            return Err(Rule::DigitCount);
+        }
+        return Ok(());
+    }
+    fn propagate(&self, cells:&mut Cells) -> Result<(), Rule> {
+        for d in 0..cells.digit_masks.len() {
+            let mask = cells.digit_masks[d];
+            let (filled, open) = ((cells.filled & mask).count(), cells.open() & mask);
+            if filled > d {
+                return Err(Rule::DigitOvercount);
+            }
+            if filled + open.count() < d {
+                return Err(Rule::DigitCount);
+            }
+            if filled == d {
+                cells.clear(open);
+            }
+            else if filled + open.count() == d {
+                cells.fill(open);
+            }
+        }
+        return Ok(());
+    }
+}
+
+/// No 2x2 block of cells is completely filled.
+#[derive(Debug)]
+pub struct NoFull2x2;
+impl Constraint for NoFull2x2 {
+    fn admits_shape(&self, shape:&Polyomino) -> bool {
+        return !shape.has_2x2();
+    }
+    fn check_partial(&self, board:&Board) -> Result<(), Rule> {
+        if !board.occupied.full_2x2_blocks(board.dim).is_empty() {
+            return Err(Rule::TwoByTwo);
+        }
+        return Ok(());
+    }
+    fn propagate(&self, cells:&mut Cells) -> Result<(), Rule> {
+        let dim = cells.dim;
+        if !cells.filled.full_2x2_blocks(dim).is_empty() {
+            return Err(Rule::TwoByTwo);
+        }
+        // A block with one open cell and no empty cell leaves it empty
+        let mut corners = (cells.filled | cells.open()).full_2x2_blocks(dim).0;
+        while corners != 0 {
+            let corner = corners & corners.wrapping_neg();
+            corners &= corners - 1;
+            let block = Bitboard(corner | corner << 1 | corner << dim | corner << (dim + 1));
+            // Blocks are listed before any is cleared
+            if (block & cells.filled).count() == 3 && !block.intersects(&cells.empty) {
+                cells.clear(block.without(&cells.filled));
+            }
+        }
+        return Ok(());
+    }
+}
+
+/// The filled cells form one orthogonally connected region.
+#[derive(Debug)]
+pub struct Connected;
+impl Constraint for Connected {
+    fn check_complete(&self, board:&Board) -> Result<(), Rule> {
+        if board.components() > 1 {
+            return Err(Rule::Connectivity);
+        }
+        return Ok(());
+    }
+    fn propagate(&self, cells:&mut Cells) -> Result<(), Rule> {
+        let dim = cells.dim;
+        let seed = match cells.filled.lowest() {
+            Some(seed) => seed,
+            None => return Ok(())
+        };
+        // Open cells out of reach of the filled cells stay empty
+        let region = (cells.filled | cells.open()).flood(&seed, dim);
+        if !cells.filled.without(&region).is_empty() {
+            return Err(Rule::Connectivity);
+        }
+        cells.clear(cells.open().without(&region));
+        return Ok(());
+    }
+}
+
+/// The filled cells can be divided into pieces of `piece_size` cells. The
+/// division itself is checked by the other constraints; the cell search uses
+/// this one to reject fillings that can never be divided.
+#[derive(Debug)]
+pub struct Divisible {
+    pub piece_size:usize
+}
+impl Constraint for Divisible {
+    fn propagate(&self, cells:&mut Cells) -> Result<(), Rule> {
+        let dim = cells.dim;
+        let open = cells.open();
+        // A region of filled cells with no open neighbor can no longer grow
+        let mut rest = cells.filled;
+        while let Some(seed) = rest.lowest() {
+            let region = cells.filled.flood(&seed, dim);
+            if !region.neighbors(dim).intersects(&open) && !region.count().is_multiple_of(self.piece_size) {
+                return Err(Rule::Division);
+            }
+            rest = rest.without(&region);
+        }
+        // Every filled cell lies in a piece within the filled and open cells,
+        // and open cells in no such piece stay empty
+        let allowed = cells.filled | open;
+        let mut covered = Bitboard::EMPTY;
+        for placement in cells.placements.iter() {
+            if placement.without(&allowed).is_empty() {
+                covered |= *placement;
+            }
+        }
+        if !cells.filled.without(&covered).is_empty() {
+            return Err(Rule::Division);
+        }
+        cells.clear(open.without(&covered));
+        return Ok(());
+    }
+}
+
+/// The first filled cell seen from the edge of a clue belongs to a pentomino
+/// of the clued class, checked once that class has been placed and again on
+/// complete assignments.
+#[derive(Debug)]
+pub struct FirstSeenPentomino {
+    pub clue:EdgeClue,
+    pub class:String
+}
+impl Constraint for FirstSeenPentomino {
+    fn check_partial(&self, board:&Board) -> Result<(), Rule> {
+        let shapes = board.shapes;
+        if !board.pentominos.iter().any(|p| shapes.class(p) == self.class) {
+            return Ok(());
+        }
+        return self.check_complete(board);
+    }
+    fn check_complete(&self, board:&Board) -> Result<(), Rule> {
+        let shapes = board.shapes;
+        match board.first_filled(&self.clue).and_then(|first| board.shape_at(&first)) {
+            Some(shape) if shapes.name(shape) == self.class => return Ok(()),
+            _ => return Err(Rule::EdgeClue)
+        }
+    }
+}
+
+/// The first filled cell seen from the edge of a clue holds the clued digit.
+#[derive(Debug)]
+pub struct FirstSeenDigit {
+    pub clue:EdgeClue,
+    pub digit:usize
+}
+impl Constraint for FirstSeenDigit {
+    fn check_complete(&self, board:&Board) -> Result<(), Rule> {
+        match board.first_filled(&self.clue).and_then(|first| board.digit(&first)) {
+            Some(d) if d == self.digit => return Ok(()),
+            _ => return Err(Rule::EdgeClue)
+        }
+    }
+    fn propagate(&self, cells:&mut Cells) -> Result<(), Rule> {
+        let dim = cells.dim;
+        let line = clue_line_mask(&self.clue, dim);
+        let mask = cells.digit_masks[self.digit];
+        // Cells that may be the first seen without breaking the clue, and
+        // filled cells that would break it
+        let candidates = (cells.filled | (cells.open() & mask)) & line;
+        let first = match clue_first_cell(&self.clue, &candidates, dim) {
+            Some(first) => first,
+            None => return Err(Rule::EdgeClue)
+        };
+        if first.intersects(&cells.filled.without(&mask)) {
+            return Err(Rule::EdgeClue);
+        }
+        let before = match self.clue.side {
+            Side::Left | Side::Top => Bitboard(line.0 & (first.0 - 1)),
+            Side::Right | Side::Bottom => Bitboard(line.0 & !(first.0 | (first.0 - 1)))
+        };
+        cells.clear(before & cells.open());
+        if candidates.count() == 1 && first.intersects(&cells.open()) {
+            cells.fill(first);
+        }
+        return Ok(());
+    }
+}
+
+/// Return the greatest common divisor of two numbers, where `gcd(0, n)` is
+/// `n`.
+pub fn gcd(a:usize, b:usize) -> usize {
+    return if b == 0 { a } else { gcd(b, a % b) };
+}
+
+/// Return the filled and empty cells of the line of a clue, or `None` if the
+/// last placement left the line unchanged, so that a partial check already
+/// made of the line still holds.
+fn changed_line(clue:&EdgeClue, board:&Board) -> Option<(Bitboard, Bitboard)> {
+    let line = clue_line_mask(clue, board.dim);
+    if !board.last().is_some_and(|p| board.shapes.mask(p).intersects(&line)) {
+        return None;
+    }
+    return Some((line & board.occupied, line.without(&board.occupied)));
+}
+
+/// The digits in the filled cells of a clue's line sum to `sum`: the filled
+/// cells may not exceed it, and the empty ones must still be able to make it
+/// up.
+#[derive(Debug)]
+pub struct LineSum {
+    pub clue:EdgeClue,
+    pub sum:usize
+}
+impl Constraint for LineSum {
+    fn check_partial(&self, board:&Board) -> Result<(), Rule> {
+        let (filled, empty) = match changed_line(&self.clue, board) {
+            Some(cells) => cells,
+            None => return Ok(())
+        };
+        let sum:usize = board.digits_of(&filled).sum();
+        if sum > self.sum || sum + board.digits_of(&empty).sum::<usize>() < self.sum {
+            return Err(Rule::EdgeClue);
+        }
+        return Ok(());
+    }
+    fn check_complete(&self, board:&Board) -> Result<(), Rule> {
+        let filled = clue_line_mask(&self.clue, board.dim) & board.occupied;
+        if board.digits_of(&filled).sum::<usize>() != self.sum {
+            return Err(Rule::EdgeClue);
+        }
+        return Ok(());
+    }
+    fn propagate(&self, cells:&mut Cells) -> Result<(), Rule> {
+        let line = clue_line_mask(&self.clue, cells.dim);
+        let sum:usize = cells.digits_of(&(line & cells.filled)).sum();
+        if sum > self.sum {
+            return Err(Rule::EdgeClue);
+        }
+        // Open cells too large for the rest of the sum stay empty
+        let open = line & cells.open();
+        let too_large = cells.with_digits(&open, |d| d > self.sum - sum);
+        cells.clear(too_large);
+        let open = open.without(&too_large);
+        let rest:usize = cells.digits_of(&open).sum();
+        if sum + rest < self.sum {
+            return Err(Rule::EdgeClue);
+        }
+        if sum + rest == self.sum {
+            cells.fill(open);
+        }
+        return Ok(());
+    }
+}
+
+/// The digits in the filled cells of a clue's line multiply to `product`:
+/// the filled cells must divide it, and it must divide the product of the
+/// filled and empty cells.
+#[derive(Debug)]
+pub struct LineProduct {
+    pub clue:EdgeClue,
+    pub product:usize
+}
+impl Constraint for LineProduct {
+    fn check_partial(&self, board:&Board) -> Result<(), Rule> {
+        let (filled, empty) = match changed_line(&self.clue, board) {
+            Some(cells) => cells,
+            None => return Ok(())
+        };
+        let product:usize = board.digits_of(&filled).product();
+        if !self.product.is_multiple_of(product) || !(product * board.digits_of(&empty).product::<usize>()).is_multiple_of(self.product) {
+            return Err(Rule::EdgeClue);
+        }
+        return Ok(());
+    }
+    fn check_complete(&self, board:&Board) -> Result<(), Rule> {
+        let filled = clue_line_mask(&self.clue, board.dim) & board.occupied;
+        if board.digits_of(&filled).product::<usize>() != self.product {
+            return Err(Rule::EdgeClue);
+        }
+        return Ok(());
+    }
+    fn propagate(&self, cells:&mut Cells) -> Result<(), Rule> {
+        let line = clue_line_mask(&self.clue, cells.dim);
+        let product:usize = cells.digits_of(&(line & cells.filled)).product();
+        if !self.product.is_multiple_of(product) {
+            return Err(Rule::EdgeClue);
+        }
+        // Open cells that the rest of the product does not divide stay empty
+        let open = line & cells.open();
+        let excluded = cells.with_digits(&open, |d| !(self.product / product).is_multiple_of(d));
+        cells.clear(excluded);
+        if !(product * cells.digits_of(&open.without(&excluded)).product::<usize>()).is_multiple_of(self.product) {
+            return Err(Rule::EdgeClue);
+        }
+        return Ok(());
+    }
+}
+
+/// The greatest common divisor of the digits in the filled cells of a clue's
+/// line is `gcd`: every filled cell must be a multiple of it, and filling
+/// every empty multiple of it must bring the divisor down to it.
+#[derive(Debug)]
+pub struct LineGcd {
+    pub clue:EdgeClue,
+    pub gcd:usize
+}
+impl Constraint for LineGcd {
+    fn check_partial(&self, board:&Board) -> Result<(), Rule> {
+        let (filled, empty) = match changed_line(&self.clue, board) {
+            Some(cells) => cells,
+            None => return Ok(())
+        };
+        if board.digits_of(&filled).any(|d| !d.is_multiple_of(self.gcd)) {
+            return Err(Rule::EdgeClue);
+        }
+        let lowest = board.digits_of(&filled).chain(board.digits_of(&empty).filter(|d| d.is_multiple_of(self.gcd))).fold(0, gcd);
+        if lowest != self.gcd {
+            return Err(Rule::EdgeClue);
+        }
+        return Ok(());
+    }
+    fn check_complete(&self, board:&Board) -> Result<(), Rule> {
+        let filled = clue_line_mask(&self.clue, board.dim) & board.occupied;
+        if board.digits_of(&filled).fold(0, gcd) != self.gcd {
+            return Err(Rule::EdgeClue);
+        }
+        return Ok(());
+    }
+    fn propagate(&self, cells:&mut Cells) -> Result<(), Rule> {
+        let line = clue_line_mask(&self.clue, cells.dim);
+        if cells.digits_of(&(line & cells.filled)).any(|d| !d.is_multiple_of(self.gcd)) {
+            return Err(Rule::EdgeClue);
+        }
+        // Open cells that are not multiples of the divisor stay empty
+        let open = line & cells.open();
+        let excluded = cells.with_digits(&open, |d| !d.is_multiple_of(self.gcd));
+        cells.clear(excluded);
+        let possible = line & (cells.filled | cells.open());
+        if cells.digits_of(&possible).fold(0, gcd) != self.gcd {
+            return Err(Rule::EdgeClue);
+        }
+        return Ok(());
+    }
+}
+
+/// A given cell holds the given digit.
+#[derive(Debug)]
+pub struct FixedDigit {
+    pub coord:Coord,
+    pub digit:usize
+}
+impl Constraint for FixedDigit {
+    fn check_complete(&self, board:&Board) -> Result<(), Rule> {
+        if board.digit(&self.coord) != Some(self.digit) {
+            return Err(Rule::Given);
+        }
+        return Ok(());
+    }
+    fn propagate(&self, cells:&mut Cells) -> Result<(), Rule> {
+        let cell = Bitboard::from_coord(&self.coord, cells.dim);
+        if cells.empty.intersects(&cell) || cells.digits_of(&cell).next() != Some(self.digit) {
+            return Err(Rule::Given);
+        }
+        cells.fill(cell.without(&cells.filled));
+        return Ok(());
+    }
+}
+
+/// Return the constraints of a Hooks puzzle with pieces of `piece_size`
+/// cells and the given digits and edge clues. Partial checks run in list
+/// order, so the cheapest and most often broken rules come first.
+pub fn hooks_constraints(piece_size:usize, givens:&[(Coord, usize)], clues:&[EdgeClue]) -> Vec<Arc<dyn Constraint>> {
+    let mut constraints:Vec<Arc<dyn Constraint>> = vec![Arc::new(NoOverlap), Arc::new(SumModulus), Arc::new(DigitCounts), Arc::new(DistinctClasses)];
+    for clue in clues.iter() {
+        if let ClueValue::Pentomino(class) = &clue.value {
+            constraints.push(Arc::new(FirstSeenPentomino{clue:clue.clone(), class:class.clone()}));
+        }
+    }
+    constraints.push(Arc::new(NoFull2x2));
+    for (coord, d) in givens.iter() {
+        constraints.push(Arc::new(FixedDigit{coord:*coord, digit:*d}));
+    }
+    for clue in clues.iter() {
+        match clue.value {
+            ClueValue::Digit(d) => constraints.push(Arc::new(FirstSeenDigit{clue:clue.clone(), digit:d})),
+            ClueValue::Sum(n) => constraints.push(Arc::new(LineSum{clue:clue.clone(), sum:n})),
+            ClueValue::Product(n) => constraints.push(Arc::new(LineProduct{clue:clue.clone(), product:n})),
+            ClueValue::Gcd(n) => constraints.push(Arc::new(LineGcd{clue:clue.clone(), gcd:n})),
+            ClueValue::Pentomino(_) => ()
+        }
+    }
+    constraints.push(Arc::new(Connected));
+    constraints.push(Arc::new(Divisible{piece_size:piece_size}));
+    return constraints;
+}
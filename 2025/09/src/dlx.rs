@@ -0,0 +1,188 @@
+/// Sparse 0/1 matrix searched with Knuth's Algorithm X using dancing links.
+///
+/// The first `primary` columns must each be covered by exactly one chosen
+/// row. The remaining secondary columns may be covered by at most one chosen
+/// row, and are never chosen as the column to branch on.
+pub struct Dlx {
+    left:Vec<usize>,
+    right:Vec<usize>,
+    up:Vec<usize>,
+    down:Vec<usize>,
+    /// Column header of each node
+    column:Vec<usize>,
+    /// Row id of each node
+    row:Vec<usize>,
+    /// Number of nodes in each column, indexed by column header
+    size:Vec<usize>
+}
+impl Dlx {
+    /// Create an empty matrix. Node 0 is the root, and nodes 1 to
+    /// `primary + secondary` are the column headers.
+    pub fn new(primary:usize, secondary:usize) -> Dlx {
+        let columns = primary + secondary;
+        let mut dlx = Dlx{left:Vec::new(), right:Vec::new(), up:Vec::new(), down:Vec::new(), column:Vec::new(), row:Vec::new(), size:vec![0; columns + 1]};
+        for i in 0..=columns {
+            dlx.up.push(i);
+            dlx.down.push(i);
+            dlx.column.push(i);
+            dlx.row.push(usize::MAX);
+            if i <= primary {
+                // Primary headers are linked in a ring with the root
+                dlx.left.push(if i == 0 { primary } else { i - 1 });
+                dlx.right.push(if i == primary { 0 } else { i + 1 });
+            }
+            else {
+                dlx.left.push(i);
+                dlx.right.push(i);
+            }
+        }
+        return dlx;
+    }
+    /// Add a row covering the given columns, identified by `row_id` in the
+    /// rows passed to the search callback.
+    pub fn add_row(&mut self, row_id:usize, columns:&[usize]) {
+        let first = self.left.len();
+        for (i, c) in columns.iter().enumerate() {
+            let header = c + 1;
+            let node = first + i;
+            self.left.push(if i == 0 { first + columns.len() - 1 } else { node - 1 });
+            self.right.push(if i == columns.len() - 1 { first } else { node + 1 });
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            let last = self.up[header];
+            self.down[last] = node;
+            self.up[header] = node;
+            self.column.push(header);
+            self.row.push(row_id);
+            self.size[header] += 1;
+        }
+    }
+    fn cover(&mut self, c:usize) {
+        self.right[self.left[c]] = self.right[c];
+        self.left[self.right[c]] = self.left[c];
+        let mut i = self.down[c];
+        while i != c {
+            let mut j = self.right[i];
+            while j != i {
+                self.down[self.up[j]] = self.down[j];
+                self.up[self.down[j]] = self.up[j];
+                self.size[self.column[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+    fn uncover(&mut self, c:usize) {
+        let mut i = self.up[c];
+        while i != c {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.column[j]] += 1;
+                self.down[self.up[j]] = j;
+                self.up[self.down[j]] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+        self.right[self.left[c]] = c;
+        self.left[self.right[c]] = c;
+    }
+    /// Search for sets of rows covering every primary column, branching on
+    /// the primary column with the fewest rows. `visit` is called once with
+    /// the chosen row ids each time a row is added, with `complete` set if
+    /// they cover every primary column; returning false prunes the branch. A
+    /// matrix with no primary columns is visited once with no rows.
+    pub fn search(&mut self, visit:&mut dyn FnMut(&[usize], bool) -> bool) {
+        if self.right[0] == 0 {
+            visit(&[], true);
+            return;
+        }
+        self.search_recurse(&mut Vec::new(), visit);
+    }
+    fn search_recurse(&mut self, chosen:&mut Vec<usize>, visit:&mut dyn FnMut(&[usize], bool) -> bool) {
+        let mut best = self.right[0];
+        let mut c = self.right[best];
+        while c != 0 {
+            if self.size[c] < self.size[best] {
+                best = c;
+            }
+            c = self.right[c];
+        }
+        self.cover(best);
+        let mut r = self.down[best];
+        while r != best {
+            chosen.push(self.row[r]);
+            let mut j = self.right[r];
+            while j != r {
+                self.cover(self.column[j]);
+                j = self.right[j];
+            }
+            let complete = self.right[0] == 0;
+            if visit(chosen, complete) && !complete {
+                self.search_recurse(chosen, visit);
+            }
+            j = self.left[r];
+            while j != r {
+                self.uncover(self.column[j]);
+                j = self.left[j];
+            }
+            chosen.pop();
+            r = self.down[r];
+        }
+        self.uncover(best);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Dlx;
+
+    /// Return every complete set of rows, sorted, and the number of visits.
+    fn solve(dlx:&mut Dlx) -> (Vec<Vec<usize>>, usize) {
+        let mut solutions:Vec<Vec<usize>> = Vec::new();
+        let mut visits = 0;
+        dlx.search(&mut |chosen, complete| {
+            visits += 1;
+            if complete {
+                let mut rows = chosen.to_vec();
+                rows.sort();
+                solutions.push(rows);
+            }
+            return true;
+        });
+        solutions.sort();
+        return (solutions, visits);
+    }
+
+    #[test]
+    fn knuth_example_has_one_cover() {
+        let rows:[&[usize]; 6] = [&[2, 4, 5], &[0, 3, 6], &[1, 2, 5], &[0, 3], &[1, 6], &[3, 4, 6]];
+        let mut dlx = Dlx::new(7, 0);
+        for (i, columns) in rows.iter().enumerate() {
+            dlx.add_row(i, columns);
+        }
+        let (solutions, _) = solve(&mut dlx);
+        assert_eq!(solutions, vec![vec![0, 3, 4]]);
+    }
+
+    #[test]
+    fn secondary_columns_are_covered_at_most_once() {
+        let mut dlx = Dlx::new(2, 1);
+        dlx.add_row(0, &[0, 2]);
+        dlx.add_row(1, &[1, 2]);
+        dlx.add_row(2, &[0]);
+        dlx.add_row(3, &[1]);
+        let (solutions, _) = solve(&mut dlx);
+        assert_eq!(solutions, vec![vec![0, 3], vec![1, 2], vec![2, 3]]);
+    }
+
+    #[test]
+    fn each_row_is_visited_once() {
+        // A single row covering the only column completes the cover at once
+        let mut dlx = Dlx::new(1, 0);
+        dlx.add_row(0, &[0]);
+        assert_eq!(solve(&mut dlx), (vec![vec![0]], 1));
+        let mut empty = Dlx::new(0, 0);
+        assert_eq!(solve(&mut empty), (vec![vec![]], 1));
+    }
+}
@@ -0,0 +1,222 @@
+use crate::bitboard::MAX_DIM;
+use crate::board::Board;
+use crate::dlx::Dlx;
+use crate::grid::{self, Coord, Hook};
+use crate::polyomino::DEFAULT_PIECE_SIZE;
+use crate::puzzle::{ClueValue, EdgeClue, Puzzle, Side};
+use crate::shape::{Placement, ShapeId};
+use crate::tree::TreeRecorder;
+use crate::{Engine, HooksSolution, SearchOptions};
+use std::time::{Duration, Instant};
+
+/// Number of tries at finding a solution with a fully clued puzzle that is
+/// uniquely solvable before giving up.
+const MAX_ATTEMPTS:usize = 100;
+
+/// Number of exact cover nodes searched for a pentomino layout of a random
+/// hook/number combination before trying another combination.
+const LAYOUT_NODE_BUDGET:usize = 200_000;
+
+/// Small seeded pseudorandom number generator (SplitMix64), so that a seed
+/// always generates the same puzzle.
+pub struct Rng(u64);
+impl Rng {
+    pub fn new(seed:u64) -> Rng {
+        return Rng(seed);
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        return z ^ (z >> 31);
+    }
+    /// Return a number in `0..n`.
+    pub fn below(&mut self, n:usize) -> usize {
+        return (self.next_u64() % n as u64) as usize;
+    }
+    pub fn shuffle<T>(&mut self, items:&mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// A generated puzzle with the solution it was generated from.
+pub struct GeneratedPuzzle {
+    pub puzzle:Puzzle,
+    pub solution:HooksSolution
+}
+
+/// Return the number of solutions of a puzzle, counting no further than
+/// `limit`, or `None` if the count is not finished within `time_limit`.
+pub fn count_solutions(puzzle:&Puzzle, limit:usize, threads:usize, time_limit:Duration) -> Result<Option<usize>, String> {
+    let deadline = Instant::now() + time_limit;
+    let hook_arrangements = crate::enumerate_hook_arrangements(puzzle);
+    let hook_number_assignments = crate::enumerate_number_assignments(puzzle, &hook_arrangements);
+    let options = SearchOptions{threads:threads, engine:Engine::Dlx, all:true, max_solutions:Some(limit), deadline:Some(deadline), ..Default::default()};
+    let solutions = crate::solve_pentominos(puzzle, &hook_number_assignments, &options, &|_, _| {})?;
+    if solutions.len() < limit && Instant::now() >= deadline {
+        return Ok(None);
+    }
+    return Ok(Some(solutions.len().min(limit)));
+}
+
+/// Generate a random solution of an unclued `dim` x `dim` puzzle: a random
+/// hook arrangement and number assignment, and a pentomino layout found by
+/// an exact cover search over placements in random order.
+pub fn random_solution(dim:usize, rng:&mut Rng) -> HooksSolution {
+    let puzzle = Puzzle::new(dim, Vec::new(), Vec::new());
+    let hook_arrangements = crate::enumerate_hook_arrangements(&puzzle);
+    let classes = grid::pentomino_classes(&puzzle);
+    let placements:Vec<Vec<Placement>> = classes.iter().map(|class| puzzle.shapes.placements(*class).collect()).collect();
+    loop {
+        let hooks = &hook_arrangements[rng.below(hook_arrangements.len())];
+        let mut assignments:Vec<Vec<usize>> = Vec::new();
+        grid::hook_number_assignment_recurse(&puzzle, hooks, &mut vec![0; dim], 0, &mut assignments, &mut TreeRecorder::disabled());
+        if assignments.is_empty() {
+            continue;
+        }
+        let assignment = assignments.swap_remove(rng.below(assignments.len()));
+        if let Some(pentominos) = random_layout(&puzzle, hooks, &assignment, &classes, &placements, rng) {
+            return HooksSolution::new(&puzzle, hooks, &assignment, &pentominos);
+        }
+    }
+}
+
+/// Search for a pentomino layout of a hook/number combination, with the
+/// placements and skips of each class tried in random order. Returns `None`
+/// if there is no layout or the node budget runs out.
+fn random_layout(puzzle:&Puzzle, hooks:&[Hook], assignment:&[usize], classes:&[ShapeId], placements:&[Vec<Placement>], rng:&mut Rng) -> Option<Vec<Placement>> {
+    let dim = puzzle.dim;
+    let digit_masks = grid::digit_masks(hooks, assignment, dim);
+    let skips_allowed = classes.len().saturating_sub(puzzle.piece_count());
+    // Each row is a class column with a placement, or `None` for skipping
+    // the class
+    let mut rows:Vec<(usize, Option<Placement>)> = Vec::new();
+    for (column, class_placements) in placements.iter().enumerate() {
+        rows.extend(class_placements.iter().filter(|p| grid::pentomino_validate(puzzle, std::slice::from_ref(*p), &digit_masks)).map(|p| (column, Some(*p))));
+        rows.push((column, None));
+    }
+    rng.shuffle(&mut rows);
+    let mut dlx = Dlx::new(classes.len(), dim * dim);
+    for (i, (column, pentomino)) in rows.iter().enumerate() {
+        let mut columns = vec![*column];
+        if let Some(pentomino) = pentomino {
+            columns.extend(puzzle.shapes.mask(pentomino).coords(dim).iter().map(|c| classes.len() + c.y as usize * dim + c.x as usize));
+        }
+        dlx.add_row(i, &columns);
+    }
+    let mut nodes = 0;
+    let mut layout:Option<Vec<Placement>> = None;
+    let mut board = Board::new(puzzle.shapes, &digit_masks);
+    dlx.search(&mut |chosen, complete| {
+        nodes += 1;
+        if layout.is_some() || nodes > LAYOUT_NODE_BUDGET {
+            return false;
+        }
+        let pentominos:Vec<Placement> = chosen.iter().filter_map(|r| rows[*r].1).collect();
+        if chosen.len() - pentominos.len() > skips_allowed {
+            return false;
+        }
+        board.follow(&pentominos);
+        if !pentominos.is_empty() && grid::pentomino_check(puzzle, &board).is_err() {
+            return false;
+        }
+        if complete {
+            layout = Some(pentominos);
+        }
+        return true;
+    });
+    return layout;
+}
+
+/// Return every clue the solution could be given: a given digit in each
+/// filled cell, and on each side of each row and column with a filled cell,
+/// either its first digit or its first pentomino, chosen at random.
+fn candidate_clues(solution:&HooksSolution, rng:&mut Rng) -> (Vec<(Coord, usize)>, Vec<EdgeClue>) {
+    let dim = solution.digits.len();
+    let mut givens:Vec<(Coord, usize)> = Vec::new();
+    for y in 0..dim {
+        for x in 0..dim {
+            if let Some(d) = solution.digits[y][x] {
+                givens.push((Coord::new(x as isize, y as isize), d));
+            }
+        }
+    }
+    let mut clues:Vec<EdgeClue> = Vec::new();
+    for side in [Side::Top, Side::Bottom, Side::Left, Side::Right] {
+        for line in 0..dim {
+            let first = side.line_cells(line, dim).into_iter().find_map(|c| solution.digits[c.y as usize][c.x as usize].map(|d| (c, d)));
+            let (coord, d) = match first {
+                Some(first) => first,
+                None => continue
+            };
+            let value = if rng.below(2) == 0 {
+                ClueValue::Digit(d)
+            }
+            else {
+                let pentomino = solution.pentominos.iter().find(|p| solution.shapes.mask(p).contains(&coord, dim)).unwrap();
+                ClueValue::Pentomino(solution.shapes.class(pentomino).to_string())
+            };
+            clues.push(EdgeClue{side:side, line:line, value:value});
+        }
+    }
+    return (givens, clues);
+}
+
+/// Generate a uniquely solvable `dim` x `dim` puzzle from a random solution.
+/// Starting from every candidate clue, clues are removed one at a time in
+/// random order, keeping each removal that leaves the solution unique. A
+/// clue is also kept if uniqueness without it is not proven within
+/// `time_limit`, so no single clue of the result can be removed unless a
+/// proof ran out of time. `progress` is called with a description of each
+/// step.
+pub fn generate(dim:usize, rng:&mut Rng, threads:usize, time_limit:Duration, progress:&dyn Fn(&str)) -> Result<GeneratedPuzzle, String> {
+    if dim == 0 || dim > MAX_DIM || !(dim * (dim + 1) / 2).is_multiple_of(DEFAULT_PIECE_SIZE) {
+        return Err(format!("Cannot generate a {}x{} puzzle: the size must be at most {} with a multiple of {} filled cells", dim, dim, MAX_DIM, DEFAULT_PIECE_SIZE));
+    }
+    for attempt in 1..=MAX_ATTEMPTS {
+        let solution = random_solution(dim, rng);
+        let (givens, clues) = candidate_clues(&solution, rng);
+        let mut puzzle = Puzzle::new(dim, givens, clues);
+        if count_solutions(&puzzle, 2, threads, time_limit)? != Some(1) {
+            progress(&format!("Attempt {}: the fully clued puzzle is not unique, trying another solution", attempt));
+            continue;
+        }
+        // Removal candidates: `true` for a given digit, `false` for an edge
+        // clue, with the value removed
+        let mut order:Vec<(bool, usize)> = (0..puzzle.givens.len()).map(|i| (true, i)).chain((0..puzzle.clues.len()).map(|i| (false, i))).collect();
+        rng.shuffle(&mut order);
+        let mut kept_givens = vec![true; puzzle.givens.len()];
+        let mut kept_clues = vec![true; puzzle.clues.len()];
+        let (all_givens, all_clues) = (puzzle.givens.clone(), puzzle.clues.clone());
+        for (n, (given, i)) in order.iter().enumerate() {
+            if *given {
+                kept_givens[*i] = false;
+            }
+            else {
+                kept_clues[*i] = false;
+            }
+            let candidate = Puzzle::new(
+                dim,
+                all_givens.iter().zip(kept_givens.iter()).filter(|(_, k)| **k).map(|(g, _)| *g).collect(),
+                all_clues.iter().zip(kept_clues.iter()).filter(|(_, k)| **k).map(|(c, _)| c.clone()).collect()
+            );
+            let count = count_solutions(&candidate, 2, threads, time_limit)?;
+            if count == Some(1) {
+                puzzle = candidate;
+            }
+            else if *given {
+                kept_givens[*i] = true;
+            }
+            else {
+                kept_clues[*i] = true;
+            }
+            let timed_out = if count.is_none() { " (uniqueness not proven in time)" } else { "" };
+            progress(&format!("Tried {} of {} clues{}, {} givens and {} edge clues left", n + 1, order.len(), timed_out, puzzle.givens.len(), puzzle.clues.len()));
+        }
+        return Ok(GeneratedPuzzle{puzzle:puzzle, solution:solution});
+    }
+    return Err(format!("No uniquely solvable {}x{} puzzle found in {} attempts", dim, dim, MAX_ATTEMPTS));
+}
@@ -1,41 +1,25 @@
-use std::collections::{HashMap,HashSet};
-use std::hash::Hash;
-use std::hash::Hasher;
+use std::collections::HashSet;
 use std::thread;
-use std::sync::{Arc,Mutex};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use crate::bitboard::Bitboard;
+use crate::checkpoint::{prefix_key, Checkpoint};
+use crate::board::Board;
+use crate::cells::{branch_cell, cell_digits, propagate, Cells};
+use crate::dlx::Dlx;
+use crate::pool::WorkQueue;
+use crate::puzzle::{EdgeClue, Puzzle, Side};
+use crate::shape::{Placement, ShapeId, ShapeTable};
+use crate::stats::{Rejections, Rule, RULES, SearchStats};
+use crate::tree::{TreeNode, TreePosition, TreeRecorder};
 
-pub const GRID_DIM:usize = 9;
-pub const OCCUPIED_CELLS:usize = 45;
-
-const ONE_POS:Coord = Coord{x:4,y:4};
-const FOUR_POS:Coord = Coord{x:3,y:1};
-const FIVE_POS:Coord = Coord{x:4,y:0};
-const EIGHT_POS:Coord = Coord{x:5,y:7};
-const NINE_POS:Coord = Coord{x:4,y:8};
-
-const THREE_COL_LO:isize = 2;
-const SEVEN_COL_HI:isize = 6;
-const SIX_ROW_LEFT:isize = 3;
-const TWO_ROW_RIGHT:isize = 5;
-const I_ROW_LEFT:isize = 0;
-const U_ROW_RIGHT:isize = 0;
-const X_ROW_RIGHT:isize = 3;
-const N_ROW_LEFT:isize = 5;
-const Z_ROW_LEFT:isize = 8;
-const V_ROW_RIGHT:isize = 8;
-
-// Since the P shape contains a fully occupied 2x2 block, it can't be placed on the grid and is omitted
-const F_BASE: &str = ".##\n##.\n.#.";
-const I_BASE: &str = "#####";
-const L_BASE: &str = "#.\n#.\n#.\n##";
-const N_BASE: &str = "##..\n.###";
-const T_BASE: &str = "###\n.#.\n.#.";
-const U_BASE: &str = "#.#\n###";
-const V_BASE: &str = "#..\n#..\n###";
-const W_BASE: &str = "#..\n##.\n.##";
-const X_BASE: &str = ".#.\n###\n.#.";
-const Y_BASE: &str = "..#.\n####";
-const Z_BASE: &str = "..#\n###\n#..";
+/// Number of pentominos placed before the remaining subtrees are handed to
+/// worker threads in `pentomino_search`
+const PREFIX_DEPTH:usize = 5;
+/// Subtrees with fewer than this many pentominos left to place are never
+/// split between worker threads
+const MIN_SPLIT_REMAINING:usize = 3;
 
 /// 2D coordinate vector
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
@@ -61,14 +45,14 @@ impl std::ops::Add<&Coord> for &Coord {
 }
 
 /// Hook
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum HookOrientation {
 	NW,
 	SW,
 	SE,
 	NE
 }
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct Hook {
 	pub dim:usize,
     pub position:Coord,
@@ -105,81 +89,106 @@ impl Hook {
 		}
 		return cells;
 	}
+	/// Return the cells of the hook as a bitboard on a `dim` x `dim` grid.
+	/// The hook must lie within the grid.
+	pub fn mask(&self, dim:usize) -> Bitboard {
+		return Bitboard::from_coords(self.cells().iter(), dim).unwrap();
+	}
 }
 
-pub fn hook_print(hooks:&Vec<Hook>) {
-    for y in 0..GRID_DIM {
-        for x in 0..GRID_DIM {
+/// Return the cells assigned each number in a hook/number combination,
+/// indexed by number.
+pub fn digit_masks(hooks:&[Hook], numbers:&[usize], dim:usize) -> Vec<Bitboard> {
+	let mut masks = vec![Bitboard::EMPTY; dim + 1];
+	for (hook, number) in hooks.iter().zip(numbers.iter()) {
+		masks[*number] |= hook.mask(dim);
+	}
+	return masks;
+}
+
+pub fn hook_print(hooks:&[Hook], dim:usize) {
+	let masks:Vec<Bitboard> = hooks.iter().map(|hook| hook.mask(dim)).collect();
+    for y in 0..dim {
+        for x in 0..dim {
             let coord = Coord::new(x as isize, y as isize);
-            let mut found = false;
-            for i in 0..hooks.len() {
-                if hooks[i].cells().contains(&coord) {
-                    print!("{} ", hooks[i].dim);
-                    found = true;
-                    break;
-                }
-                
-            }
-            if !found {
-                print!(". ");
+            match masks.iter().position(|mask| mask.contains(&coord, dim)) {
+                Some(i) => print!("{} ", hooks[i].dim),
+                None => print!(". ")
             }
         }
         println!();
     }
 }
 
-// Check hook arrangement against constraints on specified 1 and 2 cells
-pub fn hook_check(hooks:&Vec<Hook>) -> bool {
-	let verbose = false;
-	for i in 0..hooks.len() {
-		let cells = hooks[i].cells();
+// Check hook arrangement against the puzzle's constraints on 1 and 2 cells,
+// returning the name of the rule it breaks, if any. Only the number 1 fits in
+// the 1-hook, and the number 2 must be assigned to the 2-hook (3 would break
+// the 2x2 rule), so any given 1 or 2 must lie in the corresponding hook, and
+// any row or column clued with a 1 or 2 must overlap it.
+pub fn hook_violation(puzzle:&Puzzle, hooks:&[Hook]) -> Option<&'static str> {
+	for hook in hooks.iter() {
+		let cells = hook.cells();
 		for cell in cells.iter() {
-			if cell.x < 0 || cell.y < 0 || cell.x >= (GRID_DIM as isize) || cell.y >= (GRID_DIM as isize){
-				if verbose { println!("Cells of hook {} outside bounds", hooks[i].dim); }
-				return false;
+			if cell.x < 0 || cell.y < 0 || cell.x >= (puzzle.dim as isize) || cell.y >= (puzzle.dim as isize){
+				return Some("bounds");
 			}
 		}
-		// The number 2 must be assigned to the 2-hook (no other numbers work in that position,
-		// so the 2-hook must overlap the 2 row
-		if hooks[i].dim == 2 {
-			let mut row_found = false;
-			for cell in cells {
-				if cell.y == TWO_ROW_RIGHT {
-					row_found = true;
-					break;
-				}
+		if hook.dim > 2 {
+			continue;
+		}
+		for (coord, d) in puzzle.givens.iter() {
+			if *d == hook.dim && !cells.contains(coord) {
+				return Some(Rule::Given.name());
 			}
-			if !row_found {
-				if verbose { println!("Hook 2 does not overlap 2-row {}", TWO_ROW_RIGHT); }
-				return false;
+		}
+		for (clue, d) in puzzle.digit_clues() {
+			if d == hook.dim && !clue.cells(puzzle.dim).iter().any(|c| cells.contains(c)) {
+				return Some(Rule::EdgeClue.name());
 			}
 		}
-		// Check that the 1-hook is placed on the 1 position
-		else if hooks[i].dim == 1 {
-			if !cells.contains(&ONE_POS) {
-				if verbose { println!("Hook 1 is not positioned on the 1-cell {:?}", ONE_POS); }
-				return false;
+	}
+	return None;
+}
+
+/// Return a hash identifying a hook layout, the same across runs and
+/// platforms: 64-bit FNV-1a over the size, orientation and position of each
+/// hook in order.
+pub fn hook_layout_hash(hooks:&[Hook]) -> u64 {
+	let mut hash:u64 = 0xcbf29ce484222325;
+	for hook in hooks.iter() {
+		for value in [hook.dim as i64, hook.orientation as i64, hook.position.x as i64, hook.position.y as i64] {
+			for byte in value.to_le_bytes() {
+				hash ^= byte as u64;
+				hash = hash.wrapping_mul(0x100000001b3);
 			}
 		}
 	}
-	return true;
+	return hash;
+}
+
+/// Label of the node that places a hook in a recorded search tree.
+fn hook_label(hook:&Hook) -> String {
+	return format!("{}x{} {:?} at {}", hook.dim, hook.dim, hook.orientation, hook.position);
 }
 
-/// DFS to find valid hook arrangements
-pub fn hook_recurse(dim:usize, assigned:&mut Vec<Hook>, remaining_corner:Coord, solutions:&mut Vec<Vec<Hook>>) {
+/// DFS to find valid hook arrangements, recording the nodes visited in
+/// `tree`
+pub fn hook_recurse(puzzle:&Puzzle, dim:usize, assigned:&mut Vec<Hook>, remaining_corner:Coord, solutions:&mut Vec<Vec<Hook>>, tree:&mut TreeRecorder) {
 	if dim == 1 {
 		assigned.push(Hook::new(dim, Coord::new(remaining_corner.x, remaining_corner.y), HookOrientation::NE)); // orientation doesn't matter for the 1-hook
-		if hook_check(assigned) {
-			let mut solved:Vec<Hook> = Vec::new();
-			for i in 0..assigned.len() {
-				solved.push(assigned[i].clone());
-			}
-			solutions.push(solved);
+		tree.enter(|| hook_label(assigned.last().unwrap()));
+		match hook_violation(puzzle, assigned) {
+			None => {
+				tree.solution();
+				solutions.push(assigned.clone());
+			},
+			Some(rule) => tree.prune(rule)
 		}
+		tree.leave();
 		assigned.pop();
 		return
 	}
-	for orientation in vec![HookOrientation::NE, HookOrientation::NW, HookOrientation::SE, HookOrientation::SW] {
+	for orientation in [HookOrientation::NE, HookOrientation::NW, HookOrientation::SE, HookOrientation::SW] {
 		let next_hook:Hook;
 		let next_corner:Coord;
 		match orientation {
@@ -201,315 +210,106 @@ pub fn hook_recurse(dim:usize, assigned:&mut Vec<Hook>, remaining_corner:Coord,
 			}
 		}
 		assigned.push(next_hook);
-		if hook_check(assigned) {
-			hook_recurse(dim - 1, assigned, next_corner, solutions);
+		tree.enter(|| hook_label(&next_hook));
+		match hook_violation(puzzle, assigned) {
+			None => hook_recurse(puzzle, dim - 1, assigned, next_corner, solutions, tree),
+			Some(rule) => tree.prune(rule)
 		}
+		tree.leave();
 		assigned.pop();
 	}
 }
 
-/// DFS to find valid assignments of numbers to hook arrangements
-pub fn hook_number_assignment_recurse(hook_arrangement:&Vec<Hook>, number_assignments:&mut Vec<usize>, index:usize, solutions:&mut Vec<Vec<usize>>) {
-	let verbose = false;
+/// DFS to find valid assignments of numbers to hook arrangements, recording
+/// the nodes visited in `tree`
+pub fn hook_number_assignment_recurse(puzzle:&Puzzle, hook_arrangement:&[Hook], number_assignments:&mut Vec<usize>, index:usize, solutions:&mut Vec<Vec<usize>>, tree:&mut TreeRecorder) {
 	// initialize hooks that correspond to known positions
 	if index == 0 {
-		for i in 0..hook_arrangement.len() {
-			if hook_arrangement[i].cells().contains(&NINE_POS) {
-				if number_assignments[i] != 0 {return;}
-				number_assignments[i] = 9;
-				break;
-			}
-		}
-		for i in 0..hook_arrangement.len() {
-			if hook_arrangement[i].cells().contains(&EIGHT_POS) {
-				if number_assignments[i] != 0 {return;}
-				number_assignments[i] = 8;
-				break;
-			}
-		}
-		for i in 0..hook_arrangement.len() {
-			if hook_arrangement[i].cells().contains(&FIVE_POS) {
-				if number_assignments[i] != 0 {return;}
-				number_assignments[i] = 5;
-				break;
-			}
-		}
-		for i in 0..hook_arrangement.len() {
-			if hook_arrangement[i].cells().contains(&FOUR_POS) {
-				if number_assignments[i] != 0 {return;}
-				number_assignments[i] = 4;
-				break;
-			}
-		}
-		for i in 0..hook_arrangement.len() {
-			if hook_arrangement[i].cells().contains(&ONE_POS) {
-				if number_assignments[i] != 0 {return;}
-				number_assignments[i] = 1;
-				break;
+		let mut required:Vec<(Coord, usize)> = puzzle.givens.clone();
+		// Only 1 fits in the 1-hook, and 2 must be assigned to the 2-hook
+		// because while 3 will technically fit, it would violate the 2x2 rule
+		for hook in hook_arrangement.iter() {
+			if hook.dim <= 2 {
+				required.push((hook.position, hook.dim));
 			}
 		}
-		// 2 must be assigned to the 2-hook because while 3 will 
-		// technically fit, it would violate the 2x2 rule
-		for i in 0..hook_arrangement.len() {
-			if hook_arrangement[i].dim == 2 {
-				if number_assignments[i] != 0 {return;}
-				number_assignments[i] = 2;
-				break;
+		for (coord, d) in required {
+			for i in 0..hook_arrangement.len() {
+				if hook_arrangement[i].cells().contains(&coord) {
+					if number_assignments[i] == d {
+						break;
+					}
+					if number_assignments[i] != 0 || number_assignments.contains(&d) {
+						tree.prune(Rule::Given.name());
+						return;
+					}
+					number_assignments[i] = d;
+					break;
+				}
 			}
 		}
 	}
-	// A complete assignment has been reached - check that specified rows and 
-	// columns contain at least once hook cell with the required digit
+	// A complete assignment has been reached - check that clued rows and
+	// columns contain at least one hook cell with the required digit
 	else if index == hook_arrangement.len() {
-		let six_cells = hook_arrangement[number_assignments.iter().position(|&r| r == 6).unwrap()].cells();
-		let mut six_found = false;
-		for x in 0..GRID_DIM {
-			if six_cells.contains(&Coord::new(x as isize, SIX_ROW_LEFT as isize)) {
-				six_found = true;
-				break;
-			}
-		}
-		if !six_found {
-			if verbose { println!("SIX ROW {} not in 6-assigned hook cells {:?}", SIX_ROW_LEFT, six_cells); }
-			return;
-		}
-		let two_cells = hook_arrangement[number_assignments.iter().position(|&r| r == 2).unwrap()].cells();
-		let mut two_found = false;
-		for x in 0..GRID_DIM {
-			if two_cells.contains(&Coord::new(x as isize, TWO_ROW_RIGHT as isize)) {
-				two_found = true;
-				break;
+		for (clue, d) in puzzle.digit_clues() {
+			let cells = hook_arrangement[number_assignments.iter().position(|&r| r == d).unwrap()].cells();
+			if !clue.cells(puzzle.dim).iter().any(|c| cells.contains(c)) {
+				tree.prune(Rule::EdgeClue.name());
+				return;
 			}
 		}
-		if !two_found {
-			if verbose { println!("TWO ROW {} not in 2-assigned hook cells {:?}", TWO_ROW_RIGHT, two_cells); }
-			return;
-		}
-		let three_cells = hook_arrangement[number_assignments.iter().position(|&r| r == 3).unwrap()].cells();
-		let mut three_found = false;
-		for y in 0..GRID_DIM {
-			if three_cells.contains(&Coord::new(THREE_COL_LO as isize, y as isize)) {
-				three_found = true;
-				break;
-			}
-		}
-		if !three_found {
-			if verbose { println!("THREE COL {} not in 3-assigned hook cells {:?}", THREE_COL_LO, three_cells); }
-			return;
-		}
-		let seven_cells = hook_arrangement[number_assignments.iter().position(|&r| r == 7).unwrap()].cells();
-		let mut seven_found = false;
-		for y in 0..GRID_DIM {
-			if seven_cells.contains(&Coord::new(SEVEN_COL_HI as isize, y as isize)) {
-				seven_found = true;
-				break;
-			}
-		}
-		if !seven_found {
-			if verbose { println!("SEVEN COL {} not in 7-assigned hook cells {:?}", SEVEN_COL_HI, seven_cells); }
-			return;
-		}
 		// Hook/number combination okay
+		tree.solution();
 		solutions.push(number_assignments.clone());
 		return;
 	}
     // Continue if this hook as already been assigned a number
 	if number_assignments[index] != 0 {
-		hook_number_assignment_recurse(hook_arrangement, number_assignments, index + 1, solutions);
+		hook_number_assignment_recurse(puzzle, hook_arrangement, number_assignments, index + 1, solutions, tree);
 	}
     // Try all available numbers on the current hook
 	else {
 		let mut remaining_numbers:Vec<usize> = Vec::new();
-		for n in 1..=GRID_DIM {
+		for n in 1..=puzzle.dim {
 			if !number_assignments.contains(&n) {
 				remaining_numbers.push(n);
 			}
 		}
 		for n in remaining_numbers {
-			if n > hook_arrangement[index].num_cells() {
+			let hook = &hook_arrangement[index];
+			tree.enter(|| format!("{}x{} hook <- {}", hook.dim, hook.dim, n));
+			if n > hook.num_cells() {
+				tree.prune("hook size");
+				tree.leave();
 				continue;
 			}
 			let mut next_number_assignments = number_assignments.clone();
 			next_number_assignments[index] = n;
-			hook_number_assignment_recurse(hook_arrangement, &mut next_number_assignments, index + 1, solutions);
-		}
-	}
-}
-
-/// Pentomino
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub struct Pentomino {
-	pub dim_x: usize,
-	pub dim_y: usize,
-	pub cells: HashSet<Coord>,
-	pub position: Coord,
-	pub class:String
-}
-impl Hash for Pentomino {
-	fn hash<H: Hasher>(&self, state: &mut H) {
-		let mut bits:usize = 0;
-		let mut normalized = self.clone();
-		normalized.normalize();
-		for y in 0..5 {
-			for x in 0..5 {
-				if normalized.cells.contains(&Coord::new(x,y)) {
-					bits |= 1 << (x + 5*y);
-				}
-			}
-		}
-		bits.hash(state);
-	}
-}
-impl Pentomino {
-	pub fn new(input_string: &str, class:String) -> Pentomino {
-		let mut min_x:isize = -1;
-		let mut min_y:isize = -1;
-		let mut max_x:isize = -1;
-		let mut max_y:isize = -1;
-		let mut cells:HashSet<Coord> = HashSet::new();
-		let lines:Vec<&str> = input_string.split("\n").collect();
-		assert!(lines.len() > 0);
-		for y in 0..lines.len() {
-			let chars:Vec<char> = lines[y].chars().collect();
-			for x in 0..chars.len() {
-				if chars[x] == '#' {
-					cells.insert(Coord::new(x as isize,y as isize));
-					if (x as isize) < min_x || min_x == -1 {
-						min_x = x as isize ;
-					}
-					if (x as isize) > max_x || min_x == -1 {
-						max_x = x as isize ;
-					}
-					if (y as isize) < min_y || min_y == -1 {
-						min_y = y as isize ;
-					}
-					if (x as isize) > max_y || min_y == -1 {
-						max_y = y as isize ;
-					}
-				}
-			}
-		}
-		assert!(max_x - min_x + 1 > 0);
-		assert!(max_y - min_y + 1 > 0);
-		let mut pentomino = Pentomino{ dim_x: 0, dim_y: 0, cells: cells, position: Coord::new(0,0), class: class };
-		pentomino.normalize();
-		return pentomino;
-	}
-    /// Copy the pentomino with an offset position
-	pub fn copy_with_position(&self, position:Coord) -> Pentomino {
-		return Pentomino{dim_x:self.dim_x, dim_y:self.dim_y, cells:self.cells.clone(), position:position, class:self.class.clone()};
-	}
-	/// Normalize coordinates of the pentomino cells so that the bounding box
-	/// has an upper-left coordinate of (0,0)
-	pub fn normalize(&mut self) {
-		let mut next_cells:HashSet<Coord> = HashSet::new();
-		let mut min_x = 5;
-		let mut min_y = 5;
-		let mut max_x = -5;
-		let mut max_y = -5;
-		for cell in self.cells.iter() {
-			if cell.x < min_x {
-				min_x = cell.x;
-			}
-			if cell.y < min_y || min_y == -1 {
-				min_y = cell.y;
-			}
-			if cell.x > max_x || max_x == -1 {
-				max_x = cell.x;
-			}
-			if cell.y > max_y || max_y == -1 {
-				max_y = cell.y;
-			}
-		}
-		for cell in self.cells.drain() {
-			next_cells.insert(Coord::new(cell.x - min_x, cell.y - min_y));
-		}
-		self.position = Coord::new(0,0);
-		self.cells = next_cells;
-		self.dim_x = (max_x - min_x + 1) as usize;
-		self.dim_y = (max_y - min_y + 1) as usize;
-	}
-	/// Rotate cells of self by 90 degrees counterclockwise about the origin
-	pub fn rotate(&mut self) {
-		let mut next_cells:HashSet<Coord> = HashSet::new();
-		for cell in self.cells.drain() {
-			next_cells.insert(Coord::new(-cell.y, cell.x));
+			hook_number_assignment_recurse(puzzle, hook_arrangement, &mut next_number_assignments, index + 1, solutions, tree);
+			tree.leave();
 		}
-		self.cells = next_cells;
-		self.normalize();
-	}
-	/// Reflect cells of self across the y-axis
-	pub fn reflect(&mut self) {
-		let mut next_cells:HashSet<Coord> = HashSet::new();
-		for cell in self.cells.drain() {
-			next_cells.insert(Coord::new(-cell.x, cell.y));
-		}
-		self.cells = next_cells;
-		self.normalize();
-	}
-	/// Get cells offset by position
-	pub fn get_cells_with_offset(&self) -> HashSet<Coord> {
-		let mut offset_cells:HashSet<Coord> = HashSet::new();
-		for coord in self.cells.iter() {
-			offset_cells.insert(Coord{x: coord.x + self.position.x, y: coord.y + self.position.y});
-		}
-		return offset_cells;
 	}
 }
 
-/// Initialize a map of string identifiers to sets of all distinct members of 
-/// the pentomino types isomorphic up to rotation and reflection.
-pub fn init_pentominos() -> HashMap<&'static str, HashSet<Pentomino>> {
-	let mut pentomino_map:HashMap<&str, HashSet<Pentomino>> = HashMap::new();
-    // Note that the P shape is omitted due to including a 2x2 block.
-	let pentomino_shapes:HashMap<&str, &str> = HashMap::from([
-		("F", F_BASE), 
-		("I", I_BASE), 
-		("L", L_BASE), 
-		("N", N_BASE), 
-		("T", T_BASE), 
-		("U", U_BASE), 
-		("V", V_BASE),
-		("W", W_BASE), 
-		("X", X_BASE),
-		("Y", Y_BASE),
-		("Z", Z_BASE)]);
-	for key in pentomino_shapes.keys() {
-		let mut set:HashSet<Pentomino> = HashSet::new();
-		let base_pentomino = Pentomino::new(pentomino_shapes.get(key).unwrap(), key.to_string());
-		for rotations in 0..4 {
-			for reflections in 0..2 {
-				let mut pentomino = base_pentomino.clone();
-				for _ in 0..rotations {
-					pentomino.rotate();
-				}
-				for _ in 0..reflections {
-					pentomino.reflect();
-				}
-				if !set.contains(&pentomino) {
-					set.insert(pentomino);
-				}
-			}
-		}
-		pentomino_map.insert(key, set);
-	}
-	return pentomino_map;
+/// Return the piece shapes of the puzzle that can be placed on its grid, in
+/// alphabetical order. Shapes that one of the puzzle's constraints can never
+/// admit, such as the P pentomino under the 2x2 rule, are omitted.
+pub fn pentomino_classes(puzzle:&Puzzle) -> Vec<ShapeId> {
+	let shapes = puzzle.shapes;
+	return (0..shapes.shapes.len()).filter(|shape| {
+		shapes.placements(*shape).next().is_some() && puzzle.constraints.iter().all(|constraint| constraint.admits_shape(shapes.shapes[*shape].polyomino))
+	}).collect();
 }
 
-pub fn pentominos_print(pentominos:&Vec<Pentomino>) {
-    for y in 0..GRID_DIM {
-        for x in 0..GRID_DIM {
+pub fn pentominos_print(shapes:&ShapeTable, pentominos:&[Placement]) {
+    let dim = shapes.dim;
+    for y in 0..dim {
+        for x in 0..dim {
             let coord = Coord::new(x as isize, y as isize);
-            let mut found = false;
-            for i in 0..pentominos.len() {
-                if pentominos[i].get_cells_with_offset().contains(&coord) {
-                    print!("{} ", pentominos[i].class);        
-                    found = true;
-                    break;
-                }
-            }
-            if !found {
-                print!(". ");
+            match pentominos.iter().find(|p| shapes.mask(p).contains(&coord, dim)) {
+                Some(pentomino) => print!("{} ", shapes.class(pentomino)),
+                None => print!(". ")
             }
         }
         println!();
@@ -517,660 +317,870 @@ pub fn pentominos_print(pentominos:&Vec<Pentomino>) {
 }
 
 /// Find all empty regions on a grid with given pentomino positions and return
-/// the product of their areas
-pub fn pentominos_empty_cell_product(pentominos:&Vec<Pentomino>) -> usize {
-    let mut grid:HashSet<Coord> = HashSet::new();
-    let mut seen:HashSet<Coord> = HashSet::new();
-    let mut area_product = 1;
-    let dirs = vec![Coord::new(1,0), Coord::new(0,1), Coord::new(-1, 0), Coord::new(0, -1)];	
-    for y in 0..GRID_DIM {
-        for x in 0..GRID_DIM {
-            let coord = Coord::new(x as isize, y as isize);
-            for i in 0..pentominos.len() {
-                if pentominos[i].get_cells_with_offset().contains(&coord) {
-                    grid.insert(coord);
-                    break;
-                }
-            }
-        }
+/// their areas, in row-major order of their first cell
+pub fn pentominos_empty_regions(shapes:&ShapeTable, pentominos:&[Placement]) -> Vec<usize> {
+    let dim = shapes.dim;
+    let mut occupied = Bitboard::EMPTY;
+    for pentomino in pentominos.iter() {
+        occupied |= shapes.mask(pentomino);
     }
-    for y in 0..GRID_DIM {
-        for x in 0..GRID_DIM {
-            let coord = Coord::new(x as isize, y as isize);
-            if grid.contains(&coord) || seen.contains(&coord) {
-                continue;
-            }
-            // Flood fill
-            let mut frontier:Vec<Coord>;
-            let mut frontier_next:Vec<Coord> = vec![coord];
-            let mut members:HashSet<Coord> = HashSet::new();
-            while frontier_next.len() > 0 {
-                frontier = frontier_next;
-                frontier_next = Vec::new();
-                while frontier.len() > 0 {
-                    let member = frontier.pop().unwrap();
-                    if members.contains(&member) {
-                        continue;
-                    }
-                    members.insert(member);
-                    for delta in dirs.iter() {
-                        let neighbor = &member + delta;
-                        if neighbor.x < 0 || neighbor.y < 0 || neighbor.x >= GRID_DIM as isize || neighbor.y >= GRID_DIM as isize || grid.contains(&neighbor) || members.contains(&neighbor) {
-                            continue;
-                        }
-                        frontier_next.push(neighbor);
-                    }
-                }
-            }
-            if members.len() > 0 {
-                area_product *= members.len();
-                for member in members.iter() {
-                    seen.insert(*member);
-                }
-            }
-        }
+    let mut empty = Bitboard::full(dim).without(&occupied);
+    let mut areas:Vec<usize> = Vec::new();
+    // Flood fill each empty region in turn
+    while let Some(seed) = empty.lowest() {
+        let region = empty.flood(&seed, dim);
+        areas.push(region.count());
+        empty = empty.without(&region);
     }
-    return area_product;
+    return areas;
 }
 
-/// DFS of pentomino arrangements.
-pub fn pentomino_recurse(pentominos_map:&HashMap<&'static str, HashSet<Pentomino>>, assigned_pentominos:&mut Vec<Pentomino>, hook_number_assignments:&Vec<(Vec<Hook>, Vec<usize>)>, hook_number_index:usize, solutions:&mut HashSet<Vec<Pentomino>>) {
-	// Remaining pentomino classes after I,U,Z,V,N,X have been assigned
-    let remaining_pentomino_key_order: Vec<&str> = vec!["F", "L", "T", "W", "Y"];
-	// Assign the pentominos specified in row indicators first, in order: I, U, Z, V, N, X
-	// I
-    if assigned_pentominos.len() == 0 {
-        for i_pentomino in pentominos_map.get("I").unwrap() {
-            // must touch top row
-            let y_offset = 0;
-            for x in 0..GRID_DIM {
-                assigned_pentominos.push(i_pentomino.copy_with_position(Coord::new(x as isize,y_offset)));
-                if pentomino_validate(assigned_pentominos, hook_number_assignments, hook_number_index) {
-                    pentomino_recurse(pentominos_map, assigned_pentominos, hook_number_assignments, hook_number_index, solutions);
-				}
-                assigned_pentominos.pop();
+/// Return the clued pentomino classes with their clues, ordered by the
+/// distance of the clue line from the edge of the grid.
+pub fn pentomino_clue_order(puzzle:&Puzzle) -> Vec<(EdgeClue, String)> {
+	let mut clued:Vec<(EdgeClue, String)> = puzzle.pentomino_clues().iter().map(|(clue, class)| ((*clue).clone(), class.to_string())).collect();
+	clued.sort_by_key(|(clue, _)| clue.line.min(puzzle.dim - 1 - clue.line));
+	// A class clued from more than one edge is listed once, at its first clue
+	let mut seen:HashSet<String> = HashSet::new();
+	clued.retain(|(_, class)| seen.insert(class.clone()));
+	return clued;
+}
+
+/// Sort pentominos into the order solutions are reported in: clued classes in
+/// `pentomino_clue_order`, then the remaining classes alphabetically.
+pub fn pentominos_sort(puzzle:&Puzzle, pentominos:&mut [Placement]) {
+	let clued:Vec<String> = pentomino_clue_order(puzzle).into_iter().map(|(_, class)| class).collect();
+	let shapes = puzzle.shapes;
+	pentominos.sort_by_key(|p| (clued.iter().position(|c| c == shapes.class(p)).unwrap_or(clued.len()), shapes.class(p)));
+}
+
+/// Return the cells of the row or column of an edge clue.
+pub fn clue_line_mask(clue:&EdgeClue, dim:usize) -> Bitboard {
+	return if clue.side.is_row() { Bitboard::row(clue.line, dim) } else { Bitboard::column(clue.line, dim) };
+}
+
+/// Return the first cell of `occupied` encountered on the line of an edge
+/// clue, traversing from the clue's edge.
+pub fn clue_first_cell(clue:&EdgeClue, occupied:&Bitboard, dim:usize) -> Option<Bitboard> {
+	let line = *occupied & clue_line_mask(clue, dim);
+	return match clue.side {
+		Side::Left | Side::Top => line.lowest(),
+		Side::Right | Side::Bottom => line.highest()
+	};
+}
+
+/// The next decision of the pentomino DFS: the placements to try, and a
+/// class that may be left out of the arrangement instead.
+pub struct PentominoBranch {
+	pub candidates:Vec<Placement>,
+	pub skip:Option<ShapeId>
+}
+
+/// Choose the next decision of the DFS given the classes of the search, a
+/// partial assignment on a board and the classes left out of it, and return
+/// its legal placements: those that pass `pentomino_check` when placed on the
+/// board. Clued classes may only be
+/// placed across each of their clue lines. The decision is the one with the
+/// fewest legal placements among the classes that must still be placed (the
+/// clued classes, or every unused class once no more can be left out) and the
+/// uncovered cells that must be filled (the given digits, and the hooks whose
+/// number fills every cell). If there are none, the unused class with the
+/// fewest legal placements is chosen, and may also be left out. The
+/// placements found illegal are counted in `rejections` by the rule they
+/// break.
+pub fn pentomino_candidates(puzzle:&Puzzle, classes:&[ShapeId], board:&mut Board, excluded:&[ShapeId], rejections:&mut Rejections) -> PentominoBranch {
+	let dim = puzzle.dim;
+	let shapes = puzzle.shapes;
+	let occupied = board.occupied;
+	let clued = puzzle.pentomino_clues();
+	let classes:Vec<ShapeId> = classes.iter().copied().filter(|class| !excluded.contains(class) && board.shape_count(*class) == 0).collect();
+	let remaining = puzzle.piece_count() - board.pentominos.len();
+	let digit_masks = board.digit_masks;
+	let legal:Vec<Vec<Placement>> = classes.iter().map(|class| {
+		let lines:Vec<Bitboard> = clued.iter().filter(|(_, c)| *c == shapes.name(*class)).map(|(clue, _)| clue_line_mask(clue, dim)).collect();
+		let mut placements:Vec<Placement> = Vec::new();
+		for candidate in shapes.placements(*class) {
+			let mask = shapes.mask(&candidate);
+			if mask.intersects(&occupied) {
+				rejections[Rule::Overlap.index()] += 1;
+				continue;
 			}
-		}
-	}
-	// U
-    else if assigned_pentominos.len() == 1 {
-        for u_pentomino in pentominos_map.get("U").unwrap() {
-            // must touch top row
-            let y_offset = 0;
-            for x in 0..GRID_DIM {
-                assigned_pentominos.push(u_pentomino.copy_with_position(Coord::new(x as isize,y_offset as isize)));
-                if pentomino_validate(assigned_pentominos, hook_number_assignments, hook_number_index) {
-                    pentomino_recurse(pentominos_map, assigned_pentominos, hook_number_assignments, hook_number_index, solutions);
-				}
-                assigned_pentominos.pop();
+			if !lines.iter().all(|line| mask.intersects(line)) {
+				rejections[Rule::EdgeClue.index()] += 1;
+				continue;
 			}
-		}
-	}
-	// Z
-    else if assigned_pentominos.len() == 2 {
-        for z_pentomino in pentominos_map.get("Z").unwrap() {
-            // must touch bottom row
-            let y_offset = GRID_DIM - z_pentomino.dim_y;
-            for x in 0..GRID_DIM {
-                assigned_pentominos.push(z_pentomino.copy_with_position(Coord::new(x as isize,y_offset as isize)));
-                if pentomino_validate(assigned_pentominos, hook_number_assignments, hook_number_index) {
-                    pentomino_recurse(pentominos_map, assigned_pentominos, hook_number_assignments, hook_number_index, solutions);
-				}
-                assigned_pentominos.pop();
+			board.place(candidate);
+			match pentomino_check(puzzle, board) {
+				Ok(()) => placements.push(candidate),
+				Err(rule) => rejections[rule.index()] += 1
 			}
+			board.undo();
 		}
+		placements
+	}).collect();
+	// Too few classes can still be placed to complete the arrangement
+	if legal.iter().filter(|placements| !placements.is_empty()).count() < remaining {
+		return PentominoBranch{candidates:Vec::new(), skip:None};
 	}
-	// V
-    else if assigned_pentominos.len() == 3 {
-        for v_pentomino in pentominos_map.get("V").unwrap() {
-            // must touch bottom row
-            let y_offset = GRID_DIM - v_pentomino.dim_y;
-            for x in 0..GRID_DIM {
-                assigned_pentominos.push(v_pentomino.copy_with_position(Coord::new(x as isize,y_offset as isize)));
-                if pentomino_validate(assigned_pentominos, hook_number_assignments, hook_number_index) {
-                    pentomino_recurse(pentominos_map, assigned_pentominos, hook_number_assignments, hook_number_index, solutions);
-				}
-                assigned_pentominos.pop();
-			}
+	let skips_left = classes.len() > remaining;
+	let mut best:Option<Vec<Placement>> = None;
+	for (class, placements) in classes.iter().zip(legal.iter()) {
+		let must_place = !skips_left || clued.iter().any(|(_, c)| *c == shapes.name(*class));
+		if must_place && best.as_ref().is_none_or(|b| placements.len() < b.len()) {
+			best = Some(placements.clone());
 		}
 	}
-	// N
-    else if assigned_pentominos.len() == 4 {
-        for n_pentomino in pentominos_map.get("N").unwrap() {
-            // must touch N row
-			for y_offset in N_ROW_LEFT - (n_pentomino.dim_y as isize) + 1..=N_ROW_LEFT {
-            	for x in 0..GRID_DIM {
-					assigned_pentominos.push(n_pentomino.copy_with_position(Coord::new(x as isize,y_offset as isize)));
-					if pentomino_validate(assigned_pentominos, hook_number_assignments, hook_number_index) {
-						pentomino_recurse(pentominos_map, assigned_pentominos, hook_number_assignments, hook_number_index, solutions);
-					}
-					assigned_pentominos.pop();
-				}
-			}
-		}
+	let mut required = Bitboard::EMPTY;
+	for (coord, _) in puzzle.givens.iter() {
+		required |= Bitboard::from_coord(coord, dim);
 	}
-	// X
-    else if assigned_pentominos.len() == 5 {
-        for x_pentomino in pentominos_map.get("X").unwrap() {
-            // must touch X row
-			for y_offset in X_ROW_RIGHT - (x_pentomino.dim_y as isize) + 1..=X_ROW_RIGHT {
-            	for x in 0..GRID_DIM {
-					assigned_pentominos.push(x_pentomino.copy_with_position(Coord::new(x as isize,y_offset as isize)));
-					if pentomino_validate(assigned_pentominos, hook_number_assignments, hook_number_index) {
-						pentomino_recurse(pentominos_map, assigned_pentominos, hook_number_assignments, hook_number_index, solutions);
-					}
-					assigned_pentominos.pop();
-				}
-			}
+	for (d, digit_mask) in digit_masks.iter().enumerate() {
+		if digit_mask.count() == d {
+			required |= *digit_mask;
 		}
 	}
-	// Remaining pentominos
-	else if assigned_pentominos.len() < GRID_DIM {
-		let mut remaining_types:Vec<String> = Vec::new();
-		// Find the highest index number of any assigned pentomino in the 
-		// remaining_pentomino_key_order list
-		let mut remaining_start_index = 0;
-		for i in 0..assigned_pentominos.len() {
-			for j in 0..remaining_pentomino_key_order.len() {
-				if assigned_pentominos[i].class == remaining_pentomino_key_order[j].to_string() {
-					if j + 1 > remaining_start_index {
-						remaining_start_index = j+1;
-					}
-					break;
-				}
-			}
-		}
-		for i in remaining_start_index..remaining_pentomino_key_order.len() {
-			let type_key = remaining_pentomino_key_order[i];
-			let mut found = false;
-			for i in 0..assigned_pentominos.len() {
-				if assigned_pentominos[i].class == type_key.to_string() {
-					found = true;
-					break;
-				}
-			}
-			if !found {
-				remaining_types.push(type_key.to_string());
-			}
-		}
-		for type_key in remaining_types {
-			for pentomino in pentominos_map.get(type_key.as_str()).unwrap() {
-				for y in 0..GRID_DIM {
-					for x in 0..GRID_DIM {
-						assigned_pentominos.push(pentomino.copy_with_position(Coord::new(x as isize,y as isize)));
-						if pentomino_validate(assigned_pentominos, hook_number_assignments, hook_number_index) {
-							pentomino_recurse(pentominos_map, assigned_pentominos, hook_number_assignments, hook_number_index, solutions);
-						}
-						assigned_pentominos.pop();
-					}
-				}
-			}
+	for cell in required.without(&occupied).coords(dim) {
+		let covering:Vec<Placement> = legal.iter().flatten().copied().filter(|p| shapes.mask(p).contains(&cell, dim)).collect();
+		if best.as_ref().is_none_or(|b| covering.len() < b.len()) {
+			best = Some(covering);
 		}
 	}
-	// 9 pentominos assigned
-	else {
-		solutions.insert(assigned_pentominos.clone());
+	if let Some(best) = best {
+		return PentominoBranch{candidates:best, skip:None};
 	}
+	// Every remaining decision is optional
+	let (i, placements) = legal.into_iter().enumerate().min_by_key(|(_, placements)| placements.len()).unwrap();
+	return PentominoBranch{candidates:placements, skip:Some(classes[i])};
 }
 
-/// Test conditions of full and partial pentomino assigments against a given 
-/// hook/number combination
-pub fn pentomino_validate(assigned_pentominos:&Vec<Pentomino>, hook_number_assignments:&Vec<(Vec<Hook>, Vec<usize>)>, hook_number_index:usize) -> bool {
-	let verbose = false;
-	// Check if any pentominos are off the grid 
-	for i in 0..assigned_pentominos.len() {
-		let cells = assigned_pentominos[i].get_cells_with_offset();
-		for cell in cells {
-			if cell.x < 0 || cell.x >= GRID_DIM as isize || cell.y < 0 || cell.y >= GRID_DIM as isize {
-				if verbose { println!("Pentomino {} has cell off the grid with coord {:?}", assigned_pentominos[i].class, cell); }
-				return false;
-			}
-		}
+/// A subtree of the pentomino search: a partial pentomino assignment for one
+/// hook/number combination.
+pub struct PentominoTask {
+	pub combination:usize,
+	pub assigned:Vec<Placement>,
+	/// Classes left out of the arrangement
+	pub excluded:Vec<ShapeId>,
+	/// The prefix subtree this task belongs to, or `None` for the task that
+	/// expands a whole combination into prefixes
+	pub prefix:Option<Arc<PrefixProgress>>,
+	/// Position of the task's subtree in the recorded search tree
+	pub position:TreePosition
+}
+
+/// A prefix subtree of a combination, with the number of its tasks that are
+/// still queued or running.
+pub struct PrefixProgress {
+	pub key:String,
+	pub outstanding:AtomicUsize
+}
+
+/// Search backend used to place pentominos within a hook/number combination.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Engine {
+	/// Depth first search taking the most constrained decision at each node,
+	/// with subtrees split between worker threads
+	Dfs,
+	/// Exact cover search with dancing links, one combination per worker
+	Dlx
+}
+
+/// How each hook/number combination is searched.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Strategy {
+	/// Place pentominos with the search engine, checking the cells they fill
+	Pentominos,
+	/// Decide which cells are filled with the constraints' propagators, one
+	/// combination per worker, and divide each complete filling into
+	/// pentominos
+	Cells
+}
+
+/// Options for a pentomino search.
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+	pub threads:usize,
+	pub strategy:Strategy,
+	/// Search backend of the `Pentominos` strategy
+	pub engine:Engine,
+	/// Search every combination to completion instead of stopping after the
+	/// first combination with solutions
+	pub all:bool,
+	/// File to write checkpoints of the search progress to
+	pub checkpoint_path:Option<String>,
+	pub checkpoint_interval:Duration,
+	/// Progress of an earlier search to continue from
+	pub resume:Option<Checkpoint>,
+	/// Stop the search once this many solutions have been found
+	pub max_solutions:Option<usize>,
+	/// Stop the search at this time, leaving it incomplete
+	pub deadline:Option<Instant>,
+	/// Flag that stops the search, leaving it incomplete, once set by another
+	/// thread. The search also sets it when it stops for `max_solutions` or
+	/// `deadline`.
+	pub cancel:Option<Arc<AtomicBool>>,
+	/// Record the tree explored by the `Dfs` engine down to this depth below
+	/// the combinations in `SearchStats::tree`
+	pub tree_depth:Option<usize>
+}
+impl Default for SearchOptions {
+	fn default() -> SearchOptions {
+		return SearchOptions{threads:4, strategy:Strategy::Pentominos, engine:Engine::Dfs, all:false, checkpoint_path:None, checkpoint_interval:Duration::from_secs(60), resume:None, max_solutions:None, deadline:None, cancel:None, tree_depth:None};
 	}
-	// Check sums for each assigned pentomino given the specified hook/number 
-	// combination
-	for pentomino in assigned_pentominos.iter() {
-        let cells = pentomino.get_cells_with_offset();
-        let mut total = 0;
-        for coord in cells {
-			for j in 0..hook_number_assignments[hook_number_index].1.len() {
-				let hook_cells = hook_number_assignments[hook_number_index].0[j].cells();
-				if hook_cells.contains(&coord) {
-					total += hook_number_assignments[hook_number_index].1[j];
-					break;
-				}
-			}
-		}
-        if total % 5 != 0 {
-			if verbose { println!("Numeric total for pentomino {:?} is {}", pentomino, total); }
-            return false;
-		}
+}
+
+/// State shared by the worker threads of a pentomino search.
+pub struct PentominoSearch<'a> {
+	pub puzzle:&'a Puzzle,
+	/// Classes that may be placed, from `pentomino_classes`
+	pub classes:&'a [ShapeId],
+	pub hook_number_assignments:&'a [(Vec<Hook>, Vec<usize>)],
+	pub options:&'a SearchOptions,
+	/// Digit masks of each hook/number combination, from `digit_masks`
+	pub digit_masks:Vec<Vec<Bitboard>>,
+	pub prefix_depth:usize,
+	pub queue:WorkQueue<PentominoTask>,
+	/// Lowest index of a combination found to have a solution
+	pub first_solved:AtomicUsize,
+	/// Set once the whole search is to stop, polled at every node
+	pub cancelled:Arc<AtomicBool>,
+	/// Number of prefixes of each combination not yet searched to completion
+	pub remaining_prefixes:Vec<AtomicUsize>,
+	/// Completed work and solutions found
+	pub checkpoint:Mutex<Checkpoint>,
+	pub last_checkpoint:Mutex<Instant>,
+	/// Counts merged from the workers that have finished
+	pub stats:Mutex<SearchStats>
+}
+impl PentominoSearch<'_> {
+	/// Returns true if the rest of the search of a combination is no longer
+	/// needed: the search has been cancelled, or a solution has been found in
+	/// an earlier combination and not every combination is being searched.
+	/// The search is cancelled once the deadline has passed.
+	pub fn abandoned(&self, combination:usize) -> bool {
+		if self.cancelled.load(Ordering::Relaxed) {
+			return true;
+		}
+		if !self.options.all && combination > self.first_solved.load(Ordering::Relaxed) {
+			return true;
+		}
+		if self.options.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+			self.cancelled.store(true, Ordering::Relaxed);
+			return true;
+		}
+		return false;
 	}
-    
-	// Produce the grid for pentomino positions and number positions, ensure 
-	// no pentomino positions collide, and check that no digit appears more 
-	// than the required number of times. 
-    let mut number_totals = vec![0;10];
-	let mut grid_pentomino_class:HashMap<Coord, String> = HashMap::new();
-	let mut grid_number:HashMap<Coord, usize> = HashMap::new();
-	for y in 0..GRID_DIM {
-		for x in 0..GRID_DIM {
-			let coord = Coord::new(x as isize,y as isize);
-			for i in 0..assigned_pentominos.len() {
-				if assigned_pentominos[i].get_cells_with_offset().contains(&coord) {
-					if grid_pentomino_class.contains_key(&coord) {
-						if verbose {println!("Pentomino collision at position {:?}: Trying to assign {}, {} already assigned", coord, assigned_pentominos[i].class, grid_pentomino_class.get(&coord).unwrap());}
-						return false;
-					}
-					grid_pentomino_class.insert(coord.clone(), assigned_pentominos[i].class.clone());
-					// find the corresponding hook, then insert the assigned 
-					// number for that hook
-					for j in 0..hook_number_assignments[hook_number_index].0.len() {
-						if hook_number_assignments[hook_number_index].0[j].cells().contains(&coord) {
-							grid_number.insert(coord.clone(), hook_number_assignments[hook_number_index].1[j]);
-                            number_totals[hook_number_assignments[hook_number_index].1[j]] += 1;
-                        }
-					}
-				}
-			}
-		}
+	/// Depth of the search tree to record below the stage root, whose children
+	/// are the combinations.
+	fn tree_depth(&self) -> Option<usize> {
+		return self.options.tree_depth.map(|depth| depth + 1);
 	}
-    for i in 0..number_totals.len() {
-        if number_totals[i] > i {
-            if verbose {println!("Number {} appears more than {} times ({}).", i, i, number_totals[i]);}
-            return false;
-        }
-    }
-	let mut current_pentomino_classes:Vec<String> = Vec::new();
-	for i in 0..assigned_pentominos.len() {
-		if current_pentomino_classes.contains(&assigned_pentominos[i].class) {
-			if verbose { println!("Multiple pentominos of class {} found in assignment", assigned_pentominos[i].class); }
-			return false;
+	/// Record a solution of a combination, cancelling the search once enough
+	/// solutions have been found.
+	fn add_solution(&self, combination:usize, pentominos:&[Placement]) {
+		self.first_solved.fetch_min(combination, Ordering::Relaxed);
+		let mut checkpoint = self.checkpoint.lock().unwrap();
+		checkpoint.add_solution(combination, pentominos);
+		if self.options.max_solutions.is_some_and(|max| checkpoint.solutions.len() >= max) {
+			self.cancelled.store(true, Ordering::Relaxed);
 		}
-		current_pentomino_classes.push(assigned_pentominos[i].class.clone());
 	}
-	if current_pentomino_classes.contains(&"I".to_string()) {
-		// From the left
-		let mut found = false;
-		for x in 0..GRID_DIM {
-			let coord = Coord::new(x as isize, I_ROW_LEFT as isize);
-			if grid_pentomino_class.contains_key(&coord) {
-				if *grid_pentomino_class.get(&coord).unwrap() != "I".to_string() {
-					if verbose {println!("Found wrong pentomino first in I row at coord {:?}: {}", coord, grid_pentomino_class.get(&coord).unwrap());}
-					return false;
-				}
-				else {
-					found = true;
-					break;
-				}
+	/// Expand a combination into a task per valid placement of its first
+	/// `prefix_depth` pentominos, skipping prefixes already completed. The
+	/// tasks go to the front of the queue, so that combinations are searched
+	/// in turn rather than all expanded first.
+	fn expand(&self, combination:usize, stats:&mut SearchStats) {
+		let mut prefixes:Vec<Prefix> = Vec::new();
+		if !self.checkpoint.lock().unwrap().completed_combinations.contains(&combination) {
+			let mut rejections:Rejections = Default::default();
+			let mut board = Board::new(self.puzzle.shapes, &self.digit_masks[combination]);
+			let mut tree = TreeRecorder::new("", self.tree_depth());
+			tree.enter(|| combination_label(combination, &self.hook_number_assignments[combination].1));
+			pentomino_prefixes(self.puzzle, self.classes, &mut board, &mut Vec::new(), self.prefix_depth, &mut prefixes, stats, &mut rejections, &mut tree);
+			tree.leave();
+			stats.reject(combination, &rejections);
+			if let Some((position, node)) = tree.finish() {
+				stats.graft_tree(&position, node);
 			}
 		}
-		if !found {
-			if verbose {println!("I coordinate not found in expected row");}
-			return false;
-		}
-	}
-	if current_pentomino_classes.contains(&"U".to_string()) {
-		// From the right
-		let mut found = false;
-		for x in (0..GRID_DIM).rev() {
-			let coord = Coord::new(x as isize, U_ROW_RIGHT as isize);
-			if grid_pentomino_class.contains_key(&coord) {
-				if *grid_pentomino_class.get(&coord).unwrap() != "U".to_string() {
-					if verbose {println!("Found wrong pentomino first in U row at coord {:?}: {}", coord, grid_pentomino_class.get(&coord).unwrap());}
-					return false;
-				}
-				else {
-					found = true;
-					break;
+		let mut tasks:Vec<PentominoTask> = Vec::new();
+		{
+			let checkpoint = self.checkpoint.lock().unwrap();
+			for (prefix, excluded, position) in prefixes {
+				let key = prefix_key(self.puzzle.shapes, &prefix, &excluded);
+				if !checkpoint.prefix_completed(combination, &key) {
+					tasks.push(PentominoTask{combination:combination, assigned:prefix, excluded:excluded, prefix:Some(Arc::new(PrefixProgress{key:key, outstanding:AtomicUsize::new(1)})), position:position});
 				}
 			}
 		}
-		if !found {
-			if verbose {println!("U coordinate not found in expected row");}
-			return false;
+		stats.combinations.entry(combination).or_default().prefixes += tasks.len();
+		self.remaining_prefixes[combination].store(tasks.len(), Ordering::Relaxed);
+		if tasks.is_empty() {
+			self.checkpoint.lock().unwrap().complete_combination(combination);
 		}
+		self.queue.push_front(tasks);
 	}
-	if current_pentomino_classes.contains(&"X".to_string()) {
-		// From the right
-		let mut found = false;
-		for x in (0..GRID_DIM).rev() {
-			let coord = Coord::new(x as isize, X_ROW_RIGHT as isize);
-			if grid_pentomino_class.contains_key(&coord) {
-				if *grid_pentomino_class.get(&coord).unwrap() != "X".to_string() {
-					if verbose {println!("Found wrong pentomino first in X row at coord {:?}: {}", coord, grid_pentomino_class.get(&coord).unwrap());}
-					return false;
-				}
-				else {
-					found = true;
-					break;
-				}
+	/// Record a task of a prefix as searched, completing the prefix and its
+	/// combination once they have no tasks left.
+	fn finish_subtree(&self, combination:usize, prefix:&PrefixProgress) {
+		if prefix.outstanding.fetch_sub(1, Ordering::Relaxed) == 1 {
+			let mut checkpoint = self.checkpoint.lock().unwrap();
+			if self.remaining_prefixes[combination].fetch_sub(1, Ordering::Relaxed) == 1 {
+				checkpoint.complete_combination(combination);
 			}
-		}
-		if !found {
-			if verbose {println!("X coordinate not found in expected row");}
-			return false;
-		}
-	}
-	if current_pentomino_classes.contains(&"N".to_string()) {
-		// From the left
-		let mut found = false;
-		for x in 0..GRID_DIM {
-			let coord = Coord::new(x as isize, N_ROW_LEFT as isize);
-			if grid_pentomino_class.contains_key(&coord) {
-				if *grid_pentomino_class.get(&coord).unwrap() != "N".to_string() {
-					if verbose {println!("Found wrong pentomino first in N row at coord {:?}: {}", coord, grid_pentomino_class.get(&coord).unwrap());}
-					return false;
-				}
-				else {
-					found = true;
-					break;
-				}
+			else {
+				checkpoint.complete_prefix(combination, prefix.key.clone());
 			}
 		}
-		if !found {
-			if verbose {println!("N coordinate not found in expected row");}
-			return false;
-		}
 	}
-	if current_pentomino_classes.contains(&"Z".to_string()) {
-		// From the left
-		let mut found = false;
-		for x in 0..GRID_DIM {
-			let coord = Coord::new(x as isize, Z_ROW_LEFT as isize);
-			if grid_pentomino_class.contains_key(&coord) {
-				if *grid_pentomino_class.get(&coord).unwrap() != "Z".to_string() {
-					if verbose {println!("Found wrong pentomino first in Z row at coord {:?}: {}", coord, grid_pentomino_class.get(&coord).unwrap());}
-					return false;
-				}
-				else {
-					found = true;
-					break;
-				}
-			}
+	/// Write a checkpoint if one is due, or unconditionally if `force` is set.
+	fn write_checkpoint(&self, force:bool) {
+		let path = match &self.options.checkpoint_path {
+			Some(path) => path,
+			None => return
+		};
+		// Another worker is already writing a checkpoint
+		let mut last_checkpoint = match self.last_checkpoint.try_lock() {
+			Ok(last_checkpoint) => last_checkpoint,
+			Err(_) => return
+		};
+		if !force && last_checkpoint.elapsed() < self.options.checkpoint_interval {
+			return;
 		}
-		if !found {
-			if verbose {println!("Z coordinate not found in expected row");}
-			return false;
+		let checkpoint = self.checkpoint.lock().unwrap().clone();
+		if let Err(e) = checkpoint.write(path, self.puzzle, self.hook_number_assignments) {
+			eprintln!("{}", e);
 		}
+		*last_checkpoint = Instant::now();
 	}
-	if current_pentomino_classes.contains(&"V".to_string()) {
-		// From the right
-		let mut found = false;
-		for x in (0..GRID_DIM).rev() {
-			let coord = Coord::new(x as isize, V_ROW_RIGHT as isize);
-			if grid_pentomino_class.contains_key(&coord) {
-				if *grid_pentomino_class.get(&coord).unwrap() != "V".to_string() {
-					if verbose {println!("Found wrong pentomino first in V row at coord {:?}: {}", coord, grid_pentomino_class.get(&coord).unwrap());}
-					return false;
-				}
-				else {
-					found = true;
-					break;
-				}
+}
+
+/// DFS of pentomino arrangements, taking the decision chosen by
+/// `pentomino_candidates` at each node. While other workers sharing the
+/// queue are idle, the untried branches at a node are pushed back to the
+/// queue as separate subtrees of the same prefix instead of being searched
+/// here. The board holds the assignment so far, and is left as it was found.
+/// Solutions are recorded in the search checkpoint with the index of their
+/// combination, and the nodes visited and placements rejected are counted in
+/// `stats` and recorded in `tree`.
+#[allow(clippy::too_many_arguments)]
+pub fn pentomino_recurse(search:&PentominoSearch, combination:usize, prefix:&Arc<PrefixProgress>, board:&mut Board, excluded:&mut Vec<ShapeId>, stats:&mut SearchStats, tree:&mut TreeRecorder) {
+	let puzzle = search.puzzle;
+	if search.abandoned(combination) {
+		return;
+	}
+	// All pentominos assigned
+	if board.pentominos.len() == puzzle.piece_count() {
+		let mut pentominos = board.pentominos.clone();
+		pentominos_sort(puzzle, &mut pentominos);
+		tree.solution();
+		search.add_solution(combination, &pentominos);
+		return;
+	}
+	let can_split = board.pentominos.len() + MIN_SPLIT_REMAINING <= puzzle.piece_count();
+	let mut rejections:Rejections = Default::default();
+	let branch = pentomino_candidates(puzzle, search.classes, board, excluded, &mut rejections);
+	stats.reject(combination, &rejections);
+	tree_reject(tree, &rejections);
+	// Each placement, then leaving the class out if allowed
+	let mut choices = branch.candidates.into_iter().map(Some).chain(branch.skip.map(|_| None));
+	while let Some(choice) = choices.next() {
+		match choice {
+			Some(candidate) => {
+				stats.node(board.pentominos.len() + 1, puzzle.shapes.class(&candidate));
+				tree.enter(|| prefix_key(puzzle.shapes, &[candidate], &[]));
+				board.place(candidate);
+				pentomino_recurse(search, combination, prefix, board, excluded, stats, tree);
+				board.undo();
+				tree.leave();
+			},
+			None => {
+				tree.enter(|| prefix_key(puzzle.shapes, &[], &[branch.skip.unwrap()]));
+				excluded.push(branch.skip.unwrap());
+				pentomino_recurse(search, combination, prefix, board, excluded, stats, tree);
+				excluded.pop();
+				tree.leave();
 			}
 		}
-		if !found {
-			if verbose {println!("V coordinate not found in expected row");}
-			return false;
-		}
-	}
-	// Check the 2x2 rule
-	for x in 0..GRID_DIM - 1 {
-		for y in 0..GRID_DIM - 1 {
-			let mut cell_count = 0;
-			for x1 in 0..2 {
-				for y1 in 0..2 {
-					let offset_coord = Coord::new((x + x1) as isize, (y + y1) as isize);
-					if grid_pentomino_class.contains_key(&offset_coord) {
-						cell_count += 1;
+		if can_split && search.queue.hungry() {
+			// Hand the remaining branches at this node to idle workers
+			let mut subtrees:Vec<PentominoTask> = Vec::new();
+			for choice in choices.by_ref() {
+				let mut task = PentominoTask{combination:combination, assigned:board.pentominos.clone(), excluded:excluded.clone(), prefix:Some(prefix.clone()), position:TreePosition::default()};
+				match choice {
+					Some(candidate) => {
+						stats.node(task.assigned.len() + 1, puzzle.shapes.class(&candidate));
+						tree.enter(|| prefix_key(puzzle.shapes, &[candidate], &[]));
+						task.assigned.push(candidate);
+					},
+					None => {
+						tree.enter(|| prefix_key(puzzle.shapes, &[], &[branch.skip.unwrap()]));
+						task.excluded.push(branch.skip.unwrap());
 					}
 				}
+				task.position = tree.position();
+				tree.leave();
+				subtrees.push(task);
 			}
-			if cell_count > 3 {
-				if verbose {println!("2x2 rule violated at {},{}", x, y);}
-				return false;
-			}
+			prefix.outstanding.fetch_add(subtrees.len(), Ordering::Relaxed);
+			search.queue.push(subtrees);
 		}
 	}
-	// If all pentominos have been assigned, check all specified number 
-	// positions, verify that the cells are all connected, and check that the 
-	// numbers within hooks appear at the required number of times
-	if assigned_pentominos.len() == GRID_DIM {
-		// Check that the number of cells occupied by each digit matches the 
-		// expected counts
-		for i in 0..number_totals.len() {
-            if number_totals[i] != i {
-                if verbose {println!("Number {} appears {} times.", i, number_totals[i]);}
-                return false;
-            }
-        }
-        // Check specified postions, rows and columns
-		if !grid_number.contains_key(&ONE_POS) || *grid_number.get(&ONE_POS).unwrap() != 1 {
-			if verbose {println!("ONE_POS incorrect");}
-			return false;
-		}
-		if !grid_number.contains_key(&FOUR_POS) || *grid_number.get(&FOUR_POS).unwrap() != 4 {
-			if verbose {println!("FOUR_POS incorrect");}
-			return false;
-		}
-		if !grid_number.contains_key(&FIVE_POS) || *grid_number.get(&FIVE_POS).unwrap() != 5 {
-			if verbose {println!("FIVE_POS incorrect");}
-			return false;
-		}
-		if !grid_number.contains_key(&EIGHT_POS) || *grid_number.get(&EIGHT_POS).unwrap() != 8 {
-			if verbose {println!("EIGHT_POS incorrect");}
-			return false;
-		}
-		if !grid_number.contains_key(&NINE_POS) || *grid_number.get(&NINE_POS).unwrap() != 9 {
-			if verbose {println!("NINE_POS incorrect");}
-			return false;
-		}
-		// 6 from the left
-		let mut six_okay = false;
-		for x in 0..GRID_DIM {
-			let coord = Coord::new(x as isize, SIX_ROW_LEFT);
-			if grid_number.contains_key(&coord) {
-				if *grid_number.get(&coord).unwrap() != 6 {
-					if verbose {println!("SIX_ROW_LEFT found wrong value");}
-					return false;
-				}
-				else {
-					six_okay = true;
-					break;
-				}
+}
+
+/// Search a hook/number combination for pentomino arrangements as an exact
+/// cover problem. Each class is a primary column and each cell a secondary
+/// column, so every class is used at most once and every cell is covered at
+/// most once. Each valid placement of a class is a row, and unclued classes
+/// also have a row that covers only the class column, so that the classes
+/// left out of the arrangement can be skipped. The digit sum, 2x2, digit count
+/// and clue constraints are checked with `pentomino_check` as rows are
+/// chosen, on a board that follows the rows chosen. Solutions are recorded in
+/// the search checkpoint, and the combination is recorded as complete unless
+/// the search was abandoned. The nodes visited and placements rejected are
+/// counted in `stats`.
+pub fn pentomino_exact_cover(search:&PentominoSearch, combination:usize, stats:&mut SearchStats) {
+	let puzzle = search.puzzle;
+	let dim = puzzle.dim;
+	let shapes = puzzle.shapes;
+	let digit_masks = &search.digit_masks[combination];
+	if search.checkpoint.lock().unwrap().completed_combinations.contains(&combination) {
+		return;
+	}
+	let classes = search.classes;
+	let clued:Vec<String> = pentomino_clue_order(puzzle).into_iter().map(|(_, class)| class).collect();
+	let skips_allowed = classes.len().saturating_sub(puzzle.piece_count());
+	let mut rejections:Rejections = Default::default();
+	// Each row is a placement, or `None` for skipping a class
+	let mut rows:Vec<Option<Placement>> = Vec::new();
+	let mut board = Board::new(shapes, digit_masks);
+	let mut dlx = Dlx::new(classes.len(), dim * dim);
+	for (column, class) in classes.iter().enumerate() {
+		for pentomino in shapes.placements(*class) {
+			board.place(pentomino);
+			let check = pentomino_check(puzzle, &board);
+			board.undo();
+			if let Err(rule) = check {
+				rejections[rule.index()] += 1;
+				continue;
 			}
-		}
-		if !six_okay {
-			if verbose {println!("SIX_ROW_LEFT found nothing");}
-			return false;
-		}
-		// 2 from the right
-		let mut two_okay = false;
-		for x in (0..GRID_DIM).rev() {
-			let coord = Coord::new(x as isize, TWO_ROW_RIGHT);
-			if grid_number.contains_key(&coord) {
-				if *grid_number.get(&coord).unwrap() != 2 {
-					if verbose {println!("TWO_ROW_RIGHT found wrong value");}
-					return false;
-				}
-				else {
-					two_okay = true;
-					break;
-				}
+			let mut columns = vec![column];
+			let mut bits = shapes.mask(&pentomino).0;
+			while bits != 0 {
+				columns.push(classes.len() + bits.trailing_zeros() as usize);
+				bits &= bits - 1;
 			}
+			dlx.add_row(rows.len(), &columns);
+			rows.push(Some(pentomino));
 		}
-		if !two_okay {
-			if verbose {println!("TWO_ROW_RIGHT found nothing");}
-			return false;
-		}
-		// 7 from the top
-		let mut seven_okay = false;
-		for y in 0..GRID_DIM {
-			let coord = Coord::new(SEVEN_COL_HI, y as isize);
-			if grid_number.contains_key(&coord) {
-				if *grid_number.get(&coord).unwrap() != 7 {
-					if verbose {println!("SEVEN_COL_HI found wrong value");}
-					return false;
-				}
-				else {
-					seven_okay = true;
-					break;
-				}
-			}
+		if !clued.iter().any(|c| c == shapes.name(*class)) {
+			dlx.add_row(rows.len(), &[column]);
+			rows.push(None);
 		}
-		if !seven_okay {
-			if verbose {println!("SEVEN_COL_HI found nothing");}
+	}
+	dlx.search(&mut |chosen, complete| {
+		if search.abandoned(combination) {
 			return false;
 		}
-		// 3 from the bottom
-		let mut three_okay = false;
-		for y in (0..GRID_DIM).rev() {
-			let coord = Coord::new(THREE_COL_LO, y as isize);
-			if grid_number.contains_key(&coord) {
-				if *grid_number.get(&coord).unwrap() != 3 {
-					if verbose {println!("THREE_COL_LO found wrong value");}
-					return false;
-				}
-				else {
-					three_okay = true;
-					break;
-				}
-			}
-		}
-		if !three_okay {
-			if verbose {println!("THREE_COL_LO found nothing");}
+		let pentominos:Vec<Placement> = chosen.iter().filter_map(|r| rows[*r]).collect();
+		if chosen.len() - pentominos.len() > skips_allowed {
 			return false;
 		}
-		// Check that all occupied cells are connected
-		let mut connected:HashSet<Coord> = HashSet::new();
-		let dirs = vec![Coord::new(1,0), Coord::new(0,1), Coord::new(-1, 0), Coord::new(0, -1)];
-		let mut frontier:Vec<Coord>;
-		let mut frontier_next:Vec<Coord> = Vec::new();
-		frontier_next.push(ONE_POS.clone());
-		while frontier_next.len() > 0 {
-			frontier = frontier_next;
-			frontier_next = Vec::new();
-			while frontier.len() > 0 {
-				let coord = frontier.pop().unwrap();
-				if connected.contains(&coord) {
-					continue;
-				}
-				connected.insert(coord);
-				for dir in dirs.iter() {
-					let neighbor = &coord + dir;
-					if !connected.contains(&neighbor) && grid_number.contains_key(&neighbor) {
-						frontier.push(neighbor);
-					}
-				}
+		// Only a node that placed a pentomino can break a rule
+		if let Some(pentomino) = chosen.last().and_then(|r| rows[*r]) {
+			board.follow(&pentominos);
+			if let Err(rule) = pentomino_check(puzzle, &board) {
+				rejections[rule.index()] += 1;
+				return false;
+			}
+			stats.node(pentominos.len(), shapes.class(&pentomino));
+		}
+		if complete {
+			let mut pentominos = pentominos;
+			pentominos_sort(puzzle, &mut pentominos);
+			search.add_solution(combination, &pentominos);
+		}
+		return true;
+	});
+	stats.reject(combination, &rejections);
+	if !search.abandoned(combination) {
+		search.checkpoint.lock().unwrap().complete_combination(combination);
+	}
+}
+
+/// Search a hook/number combination cell by cell. Each node runs the
+/// propagators of the puzzle's constraints until no more cells are decided,
+/// then branches on filling or emptying an open cell from `branch_cell`.
+/// Complete fillings are divided into pentominos by `pentomino_divide`.
+/// Solutions are recorded in the search checkpoint, and the combination is
+/// recorded as complete unless the search was abandoned. The nodes visited
+/// and the rules that refuted them are counted in `stats`.
+pub fn cell_search(search:&PentominoSearch, combination:usize, stats:&mut SearchStats) {
+	let puzzle = search.puzzle;
+	if search.checkpoint.lock().unwrap().completed_combinations.contains(&combination) {
+		return;
+	}
+	let digit_masks = &search.digit_masks[combination];
+	let mut rejections:Rejections = Default::default();
+	// Placements that pass `pentomino_check` on their own, indexed by their
+	// first cell in row-major order
+	let mut placements:Vec<Vec<Placement>> = vec![Vec::new(); puzzle.dim * puzzle.dim];
+	let mut board = Board::new(puzzle.shapes, digit_masks);
+	for class in search.classes.iter() {
+		for pentomino in puzzle.shapes.placements(*class) {
+			board.place(pentomino);
+			let check = pentomino_check(puzzle, &board);
+			board.undo();
+			match check {
+				Ok(()) => placements[puzzle.shapes.mask(&pentomino).0.trailing_zeros() as usize].push(pentomino),
+				Err(rule) => rejections[rule.index()] += 1
 			}
 		}
-		if connected.len() != OCCUPIED_CELLS {
-			if verbose {println!("Only {} cells connected to ONE_POS", connected.len());}
+	}
+	let digits = cell_digits(digit_masks, puzzle.dim);
+	let masks:Vec<Bitboard> = placements.iter().flatten().map(|pentomino| puzzle.shapes.mask(pentomino)).collect();
+	cell_recurse(search, combination, Cells::new(puzzle.dim, digit_masks, &digits, &masks), &placements, &mut board, stats, &mut rejections);
+	stats.reject(combination, &rejections);
+	if !search.abandoned(combination) {
+		search.checkpoint.lock().unwrap().complete_combination(combination);
+	}
+}
+
+/// DFS of the cell search from the cells decided so far, with an empty board
+/// for dividing complete fillings.
+fn cell_recurse(search:&PentominoSearch, combination:usize, mut cells:Cells, placements:&[Vec<Placement>], board:&mut Board, stats:&mut SearchStats, rejections:&mut Rejections) {
+	if search.abandoned(combination) {
+		return;
+	}
+	stats.cell_nodes += 1;
+	if let Err(rule) = propagate(&search.puzzle.constraints, &mut cells) {
+		rejections[rule.index()] += 1;
+		return;
+	}
+	match branch_cell(&cells) {
+		Some(cell) => {
+			let mut filled = cells;
+			filled.fill(cell);
+			cell_recurse(search, combination, filled, placements, board, stats, rejections);
+			let mut empty = cells;
+			empty.clear(cell);
+			cell_recurse(search, combination, empty, placements, board, stats, rejections);
+		},
+		None => pentomino_divide(search, combination, &cells.filled, placements, board, stats, rejections)
+	}
+}
+
+/// Divide the filled cells of a complete filling into pentominos, covering
+/// the first uncovered cell in row-major order at each node with each
+/// placement starting there that lies within the filled cells. The board
+/// holds the pentominos placed so far, and each placement is checked with
+/// `pentomino_check`. Each division is recorded as a solution.
+fn pentomino_divide(search:&PentominoSearch, combination:usize, filled:&Bitboard, placements:&[Vec<Placement>], board:&mut Board, stats:&mut SearchStats, rejections:&mut Rejections) {
+	let puzzle = search.puzzle;
+	if search.abandoned(combination) {
+		return;
+	}
+	let uncovered = filled.without(&board.occupied);
+	if uncovered.is_empty() {
+		let mut pentominos = board.pentominos.clone();
+		pentominos_sort(puzzle, &mut pentominos);
+		search.add_solution(combination, &pentominos);
+		return;
+	}
+	for pentomino in placements[uncovered.0.trailing_zeros() as usize].iter() {
+		if !puzzle.shapes.mask(pentomino).without(&uncovered).is_empty() {
+			continue;
+		}
+		board.place(*pentomino);
+		match pentomino_check(puzzle, board) {
+			Ok(()) => {
+				stats.node(board.pentominos.len(), puzzle.shapes.class(pentomino));
+				pentomino_divide(search, combination, filled, placements, board, stats, rejections);
+			},
+			Err(rule) => rejections[rule.index()] += 1
+		}
+		board.undo();
+	}
+}
+
+/// Test conditions of full and partial pentomino assigments against the cells
+/// assigned each number by a hook/number combination, placing the pentominos
+/// on a board one at a time and checking each placement
+pub fn pentomino_validate(puzzle:&Puzzle, assigned_pentominos:&[Placement], digit_masks:&[Bitboard]) -> bool {
+	let mut board = Board::new(puzzle.shapes, digit_masks);
+	for pentomino in assigned_pentominos.iter() {
+		board.place(*pentomino);
+		if pentomino_check(puzzle, &board).is_err() {
 			return false;
 		}
 	}
 	return true;
 }
 
-/// Perform DFS to find valid pentomino arrangements given a hook/number 
-/// combination. Lower branches of the tree are passed to child threads for 
-/// evaluation in parallel.
-pub fn pentomino_permutations(pentominos_map:&HashMap<&'static str, HashSet<Pentomino>>,  hook_number_assignments:&Vec<(Vec<Hook>, Vec<usize>)>, hook_number_index:usize, threads:usize) -> HashSet<Vec<Pentomino>> {
-    let mut pentomino_results:HashSet<Vec<Pentomino>> = HashSet::new();    
-    let mut child_results:Vec<HashSet<Vec<Pentomino>>> = Vec::new();
-    for _i in 0..threads {
-        child_results.push(HashSet::new());
-    }
-    let mut handles = Vec::new();
-    let mut added = 0;
-    let results_arc = Arc::new(Mutex::new(child_results));
-	let pentominos_map_arc = Arc::new(pentominos_map.clone());
-	let hook_number_assignments_arc = Arc::new(hook_number_assignments.clone());
+/// Test the assignment on a board against the puzzle's constraints after its
+/// last placement, returning the rule broken by the first constraint it
+/// fails. The board before that placement must have passed. Complete
+/// assignments are also checked against the constraints' complete checks once
+/// every partial check has passed.
+pub fn pentomino_check(puzzle:&Puzzle, board:&Board) -> Result<(), Rule> {
+	for constraint in puzzle.constraints.iter() {
+		constraint.check_partial(board)?;
+	}
+	if board.pentominos.len() == puzzle.piece_count() {
+		for constraint in puzzle.constraints.iter() {
+			constraint.check_complete(board)?;
+		}
+	}
+	return Ok(());
+}
+
+/// A partial assignment of pentominos, with the classes left out of it and
+/// its position in the recorded search tree.
+pub type Prefix = (Vec<Placement>, Vec<ShapeId>, TreePosition);
 
-    // Iterate over members/positions of I, U, Z, V, N
-	for i_pentomino_base in pentominos_map.get("I").unwrap().iter() {
-		for i_x in 0..GRID_DIM {
-			let i_pentomino = i_pentomino_base.copy_with_position(Coord::new(i_x as isize, 0));
-			for u_pentomino_base in pentominos_map.get("U").unwrap().iter() {
-				for u_x in 0..GRID_DIM {
-					let u_pentomino = u_pentomino_base.copy_with_position(Coord::new(u_x as isize, 0));
-                    if !pentomino_validate(&vec![i_pentomino.clone(), u_pentomino.clone()], hook_number_assignments, hook_number_index) {
-                        continue;
-                    }
-					for z_pentomino_base in pentominos_map.get("Z").unwrap().iter() {
-						for z_x in 0..GRID_DIM {
-							let z_pentomino = z_pentomino_base.copy_with_position(Coord::new(z_x as isize, (GRID_DIM - z_pentomino_base.dim_y) as isize));
-							if !pentomino_validate(&vec![i_pentomino.clone(), u_pentomino.clone(), z_pentomino.clone()], hook_number_assignments, hook_number_index) {
-                                continue;
-                            }
-                            for v_pentomino_base in pentominos_map.get("V").unwrap().iter() {
-								for v_x in 0..GRID_DIM {
-									let v_pentomino = v_pentomino_base.copy_with_position(Coord::new(v_x as isize, (GRID_DIM - v_pentomino_base.dim_y) as isize));
-									if !pentomino_validate(&vec![i_pentomino.clone(), u_pentomino.clone(), z_pentomino.clone(), v_pentomino.clone()], hook_number_assignments, hook_number_index) {
-                                        continue;
-                                    }
-                                    for n_pentomino_base in pentominos_map.get("N").unwrap().iter() {
-                                        for n_y in N_ROW_LEFT - (n_pentomino_base.dim_y as isize) + 1..=N_ROW_LEFT {
-                                            for n_x in 0..GRID_DIM {
-                                                let n_pentomino = n_pentomino_base.copy_with_position(Coord::new(n_x as isize, n_y));
-                                                if !pentomino_validate(&vec![i_pentomino.clone(), u_pentomino.clone(), z_pentomino.clone(), v_pentomino.clone(), n_pentomino.clone()], hook_number_assignments, hook_number_index) {
-                                                    continue;
-                                                }
-                                                let pentominos_map_arc_clone = Arc::clone(&pentominos_map_arc);
-                                                let hook_number_assignments_arc_clone = Arc::clone(&hook_number_assignments_arc);
-                                                let results_arc_clone = Arc::clone(&results_arc);
-                                                let i_pentomino_clone = i_pentomino.clone();
-                                                let u_pentomino_clone = u_pentomino.clone();
-                                                let z_pentomino_clone = z_pentomino.clone();
-                                                let v_pentomino_clone = v_pentomino.clone();
-                                                let n_pentomino_clone = n_pentomino.clone();
-                                                let handle = thread::spawn(move || {
-                                                    let mut initial_assignments = vec![i_pentomino_clone, u_pentomino_clone, z_pentomino_clone, v_pentomino_clone, n_pentomino_clone];
-                                                    let mut solutions: HashSet<Vec<Pentomino>> = HashSet::new();
-                                                    pentomino_recurse(&pentominos_map_arc_clone, &mut initial_assignments, &hook_number_assignments_arc_clone, hook_number_index, &mut solutions);
-                                                    let mut c_r = results_arc_clone.lock().unwrap();
-                                                    (*c_r)[added] = solutions;
-                                                });
-                                                handles.push(handle);
-                                                added += 1;
-                                                if added == threads {
-                                                    // Execute the batch
-                                                    while handles.len() > 0 {
-                                                        let handle2 = handles.remove(0);
-                                                        handle2.join().unwrap();
-                                                    }
-                                                    let batchresult = results_arc.lock().unwrap();
-                                                    for i in 0..added {
-                                                        for result in batchresult[i].iter() {
-                                                            pentomino_results.insert(result.clone());
-                                                        }
-                                                    }
-                                                    added = 0;
-                                                }
-                                            }
-                                        }
-                                    }
-								}
+/// Find all partial assignments of `depth` pentominos reached by the DFS,
+/// with the classes left out of each, counting the nodes visited in `stats`
+/// and `tree` and the placements rejected in `rejections` and `tree`.
+#[allow(clippy::too_many_arguments)]
+pub fn pentomino_prefixes(puzzle:&Puzzle, classes:&[ShapeId], board:&mut Board, excluded:&mut Vec<ShapeId>, depth:usize, prefixes:&mut Vec<Prefix>, stats:&mut SearchStats, rejections:&mut Rejections, tree:&mut TreeRecorder) {
+	if board.pentominos.len() == depth {
+		prefixes.push((board.pentominos.clone(), excluded.clone(), tree.position()));
+		return;
+	}
+	let mut node_rejections:Rejections = Default::default();
+	let branch = pentomino_candidates(puzzle, classes, board, excluded, &mut node_rejections);
+	for (count, n) in rejections.iter_mut().zip(node_rejections.iter()) {
+		*count += n;
+	}
+	tree_reject(tree, &node_rejections);
+	for candidate in branch.candidates {
+		stats.node(board.pentominos.len() + 1, puzzle.shapes.class(&candidate));
+		tree.enter(|| prefix_key(puzzle.shapes, &[candidate], &[]));
+		board.place(candidate);
+		pentomino_prefixes(puzzle, classes, board, excluded, depth, prefixes, stats, rejections, tree);
+		board.undo();
+		tree.leave();
+	}
+	if let Some(class) = branch.skip {
+		tree.enter(|| prefix_key(puzzle.shapes, &[], &[class]));
+		excluded.push(class);
+		pentomino_prefixes(puzzle, classes, board, excluded, depth, prefixes, stats, rejections, tree);
+		excluded.pop();
+		tree.leave();
+	}
+}
+
+/// Label of the node for a hook/number combination in a recorded search tree.
+fn combination_label(combination:usize, assignment:&[usize]) -> String {
+	return format!("combination {} {:?}", combination, assignment);
+}
+
+/// Count the placements rejected at the current node of a recorded tree.
+fn tree_reject(tree:&mut TreeRecorder, rejections:&Rejections) {
+	for (rule, n) in RULES.iter().zip(rejections.iter()) {
+		tree.reject(rule.name(), *n);
+	}
+}
+
+/// Search for pentomino arrangements over the given hook/number combinations
+/// with a pool of worker threads. Each combination starts as one task, which
+/// is expanded into a task per partial assignment of the first pentominos
+/// reached by the DFS, and subtrees are split further between workers as they
+/// become idle. Unless `options.all` is set, tasks for later combinations are
+/// abandoned once a combination yields a solution, so only the first
+/// combination with solutions is searched to completion. The whole search
+/// stops once it is cancelled through `options.cancel`, `options.max_solutions`
+/// solutions have been found or `options.deadline` has passed: every worker
+/// polls the cancellation at each node and the queued tasks are dropped.
+/// `progress` is called with the number of tasks completed and the number
+/// created so far as the search proceeds.
+///
+/// Completed combinations and prefixes and the solutions found are written to
+/// `options.checkpoint_path` periodically and at the end of the search, and
+/// the search skips work recorded as complete in `options.resume`.
+///
+/// Returns the solutions found with the index of their combination, ordered
+/// by combination, and the search statistics gathered by every worker.
+pub fn pentomino_search(puzzle:&Puzzle, classes:&[ShapeId], hook_number_assignments:&[(Vec<Hook>, Vec<usize>)], options:&SearchOptions, progress:&(dyn Fn(usize, usize) + Sync)) -> (Vec<(usize, Vec<Placement>)>, SearchStats) {
+	let checkpoint = match &options.resume {
+		Some(checkpoint) => checkpoint.clone(),
+		None => Checkpoint::new(hook_number_assignments.len())
+	};
+	let first_solved = checkpoint.solutions.iter().map(|(combination, _)| *combination).min().unwrap_or(usize::MAX);
+	let found = checkpoint.solutions.len();
+	let search = PentominoSearch{
+		puzzle:puzzle,
+		classes:classes,
+		hook_number_assignments:hook_number_assignments,
+		options:options,
+		digit_masks:hook_number_assignments.iter().map(|(hooks, numbers)| digit_masks(hooks, numbers, puzzle.dim)).collect(),
+		prefix_depth:PREFIX_DEPTH.min(puzzle.piece_count()),
+		queue:WorkQueue::new((0..hook_number_assignments.len()).map(|i| PentominoTask{combination:i, assigned:Vec::new(), excluded:Vec::new(), prefix:None, position:TreePosition::default()}).collect()),
+		first_solved:AtomicUsize::new(first_solved),
+		cancelled:options.cancel.clone().unwrap_or_default(),
+		remaining_prefixes:hook_number_assignments.iter().map(|_| AtomicUsize::new(0)).collect(),
+		checkpoint:Mutex::new(checkpoint),
+		last_checkpoint:Mutex::new(Instant::now()),
+		stats:Mutex::new(SearchStats::default())
+	};
+	if options.max_solutions.is_some_and(|max| found >= max) {
+		search.cancelled.store(true, Ordering::Relaxed);
+	}
+	thread::scope(|scope| {
+		for _ in 0..options.threads {
+			let search = &search;
+			scope.spawn(move || {
+				let mut stats = SearchStats::default();
+				if options.tree_depth.is_some() {
+					stats.tree = Some(TreeNode::new(String::from("pentominos")));
+				}
+				while let Some(mut task) = search.queue.take() {
+					// Abandoned tasks are dropped without being recorded as
+					// complete
+					if !search.abandoned(task.combination) {
+						let task_start = Instant::now();
+						let nodes_before = stats.total_nodes();
+						if options.strategy == Strategy::Cells {
+							cell_search(search, task.combination, &mut stats);
+						}
+						else if options.engine == Engine::Dlx {
+							pentomino_exact_cover(search, task.combination, &mut stats);
+						}
+						else if let Some(prefix) = &task.prefix {
+							let mut board = Board::with_pentominos(puzzle.shapes, &search.digit_masks[task.combination], &task.assigned);
+							let mut tree = TreeRecorder::at(&task.position, search.tree_depth());
+							pentomino_recurse(search, task.combination, prefix, &mut board, &mut task.excluded, &mut stats, &mut tree);
+							if let Some((position, node)) = tree.finish() {
+								stats.graft_tree(&position, node);
+							}
+							// An abandoned subtree may have been cut short
+							if !search.abandoned(task.combination) {
+								search.finish_subtree(task.combination, prefix);
 							}
 						}
+						else {
+							search.expand(task.combination, &mut stats);
+						}
+						stats.task(task.combination, task_start, nodes_before);
 					}
+					// Workers stop as soon as their running tasks notice
+					if search.cancelled.load(Ordering::Relaxed) {
+						search.queue.clear();
+					}
+					search.queue.finish();
+					let (completed, total) = search.queue.progress();
+					progress(completed, total);
+					search.write_checkpoint(false);
 				}
+				search.stats.lock().unwrap().merge(&stats);
+			});
+		}
+	});
+	search.write_checkpoint(true);
+	let first_solved = search.first_solved.load(Ordering::Relaxed);
+	let mut stats = search.stats.into_inner().unwrap();
+	let checkpoint = search.checkpoint.into_inner().unwrap();
+	let mut solutions:Vec<(usize, Vec<Placement>)> = checkpoint.solutions.into_iter().filter(|(combination, _)| options.all || *combination == first_solved).collect();
+	// Solutions are recorded in the order workers find them
+	solutions.sort_by_key(|(combination, pentominos)| (*combination, pentominos.iter().map(|p| (puzzle.shapes.class(p), p.y, p.x)).collect::<Vec<_>>()));
+	// Workers may find more solutions before they notice the search was
+	// cancelled
+	solutions.truncate(options.max_solutions.unwrap_or(usize::MAX));
+	for (combination, _) in solutions.iter() {
+		stats.combinations.entry(*combination).or_default().solutions += 1;
+	}
+	return (solutions, stats);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Engine, SearchOptions, Strategy};
+	use crate::constraint::gcd;
+	use crate::generate::{generate, Rng};
+	use crate::puzzle::{ClueValue, Puzzle};
+	use crate::HooksSolution;
+	use std::time::Duration;
+
+	/// 5x5 puzzle with two givens and 1149 solutions
+	const GIVENS_5X5:&str = "
+. . . . . . .
+. . . . . . .
+. . . . 3 . .
+. . . . . 4 .
+. . . . . . .
+. . . . . . .
+. . . . . . .
+";
+
+	/// Return every solution of the puzzle found by a strategy and engine.
+	fn solve(puzzle:&Puzzle, strategy:Strategy, engine:Engine) -> Vec<HooksSolution> {
+		let combinations = crate::enumerate_number_assignments(puzzle, &crate::enumerate_hook_arrangements(puzzle));
+		let options = SearchOptions{threads:2, strategy:strategy, engine:engine, all:true, ..Default::default()};
+		return crate::solve_pentominos(puzzle, &combinations, &options, &|_, _| {}).unwrap();
+	}
+
+	/// Return solutions as their hooks, digits and sorted pentominos, in a
+	/// fixed order.
+	fn keys<'a>(solutions:impl Iterator<Item = &'a HooksSolution>) -> Vec<String> {
+		let mut found:Vec<String> = solutions.map(|solution| {
+			let mut pentominos = solution.pentominos.clone();
+			pentominos.sort();
+			return format!("{:?} {:?} {:?}", solution.hooks, solution.digits, pentominos);
+		}).collect();
+		found.sort();
+		return found;
+	}
+
+	/// Return every solution of the puzzle found by a strategy and engine, in
+	/// the form given by `keys`.
+	fn solutions(puzzle:&Puzzle, strategy:Strategy, engine:Engine) -> Vec<String> {
+		return keys(solve(puzzle, strategy, engine).iter());
+	}
+
+	#[test]
+	fn dfs_and_dlx_find_the_same_solutions() {
+		let puzzle = Puzzle::parse(GIVENS_5X5).unwrap();
+		let dfs = solutions(&puzzle, Strategy::Pentominos, Engine::Dfs);
+		assert_eq!(dfs.len(), 1149);
+		assert_eq!(dfs, solutions(&puzzle, Strategy::Pentominos, Engine::Dlx));
+	}
+
+	#[test]
+	fn cells_and_dfs_find_the_same_solutions() {
+		let puzzle = Puzzle::parse(GIVENS_5X5).unwrap();
+		assert_eq!(solutions(&puzzle, Strategy::Cells, Engine::Dfs), solutions(&puzzle, Strategy::Pentominos, Engine::Dfs));
+		let generated = generate(5, &mut Rng::new(7), 2, Duration::from_secs(10), &|_| {}).unwrap().puzzle;
+		let dfs = solutions(&generated, Strategy::Pentominos, Engine::Dfs);
+		assert_eq!(dfs.len(), 1);
+		assert_eq!(solutions(&generated, Strategy::Cells, Engine::Dfs), dfs);
+	}
+
+	#[test]
+	fn line_clues_keep_the_solutions_that_satisfy_them() {
+		let unclued = solve(&Puzzle::parse(GIVENS_5X5).unwrap(), Strategy::Pentominos, Engine::Dfs);
+		// Clues as (row, column, token) in the description, including the border
+		let variants:[&[(usize, usize, &str)]; 3] = [
+			&[(2, 0, "sum=15"), (0, 5, "prod=320")],
+			&[(6, 3, "sum=11"), (4, 0, "gcd=3")],
+			&[(3, 6, "prod=40"), (0, 4, "gcd=3")]];
+		for clues in variants.iter() {
+			let mut rows:Vec<Vec<&str>> = GIVENS_5X5.lines().filter(|line| !line.is_empty()).map(|line| line.split(' ').collect()).collect();
+			for (y, x, token) in clues.iter() {
+				rows[*y][*x] = token;
 			}
+			let puzzle = Puzzle::parse(&rows.iter().map(|row| row.join(" ")).collect::<Vec<String>>().join("\n")).unwrap();
+			let expected = keys(unclued.iter().filter(|solution| puzzle.clues.iter().all(|clue| {
+				let line = clue.cells(puzzle.dim).into_iter().filter_map(|c| solution.digits[c.y as usize][c.x as usize]);
+				return match clue.value {
+					ClueValue::Sum(n) => line.sum::<usize>() == n,
+					ClueValue::Product(n) => line.product::<usize>() == n,
+					ClueValue::Gcd(n) => line.fold(0, gcd) == n,
+					_ => true
+				};
+			})));
+			assert!(!expected.is_empty());
+			assert_eq!(solutions(&puzzle, Strategy::Pentominos, Engine::Dfs), expected);
+			assert_eq!(solutions(&puzzle, Strategy::Pentominos, Engine::Dlx), expected);
+			assert_eq!(solutions(&puzzle, Strategy::Cells, Engine::Dfs), expected);
 		}
-    }
-    // Run any remaining jobs
-    if added > 0 {
-        while handles.len() > 0 {
-            let handle = handles.remove(0);
-            handle.join().unwrap();
-        }
-        let batchresult = results_arc.lock().unwrap();
-		for i in 0..added {
-			for result in batchresult[i].iter() {
-				pentomino_results.insert(result.clone());
-			}
-		}
-    }
-	return pentomino_results;
+	}
 }
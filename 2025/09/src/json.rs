@@ -0,0 +1,261 @@
+use crate::grid::{self, Coord, Hook, HookOrientation};
+use crate::polyomino::parse_shape;
+use crate::puzzle::Puzzle;
+use crate::shape::Placement;
+use crate::stats::{SearchStats, StatsJson};
+use crate::{verify_assignment, verify_hooks, verify_solution, HooksSolution};
+use serde::{Deserialize, Serialize};
+
+/// A hook arrangement and the number assigned to each of its hooks
+pub type Combination = (Vec<Hook>, Vec<usize>);
+
+/// Solutions written by `--format json`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SolutionsJson {
+    /// Grid dimension of the puzzle
+    pub dim:usize,
+    pub solutions:Vec<SolutionJson>,
+    /// Counts gathered by the search, written with `--stats`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats:Option<StatsJson>
+}
+
+/// A solution written by `--format json`. Coordinates are `[x, y]` with
+/// `[0, 0]` the upper-left cell.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SolutionJson {
+    pub hooks:Vec<HookJson>,
+    /// Number assigned to each hook, in the same order as `hooks`
+    pub assignment:Vec<usize>,
+    pub pentominos:Vec<PentominoJson>,
+    /// Digit in each cell, indexed `[y][x]`, or `null` for an empty cell
+    pub digits:Vec<Vec<Option<usize>>>,
+    /// Areas of the empty regions, in row-major order of their first cell
+    pub empty_regions:Vec<usize>,
+    /// Product of the areas of the empty regions
+    pub product:usize
+}
+
+/// Hook arrangements written by `sept2025 hooks`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HookArrangementsJson {
+    /// Grid dimension of the puzzle
+    pub dim:usize,
+    pub arrangements:Vec<Vec<HookJson>>
+}
+
+/// Hook/number combinations written by `sept2025 assign`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CombinationsJson {
+    /// Grid dimension of the puzzle
+    pub dim:usize,
+    pub combinations:Vec<CombinationJson>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CombinationJson {
+    pub hooks:Vec<HookJson>,
+    /// Number assigned to each hook, in the same order as `hooks`
+    pub assignment:Vec<usize>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HookJson {
+    pub dim:usize,
+    /// Cell at the corner of the hook
+    pub corner:[isize; 2],
+    /// Direction the corner points: `NW`, `NE`, `SW` or `SE`
+    pub orientation:String
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PentominoJson {
+    pub class:String,
+    /// Rows of the pentomino's bounding box, with `#` for its cells
+    pub orientation:Vec<String>,
+    /// Upper-left cell of the bounding box
+    pub position:[isize; 2],
+    /// Sum of the digits within the pentomino
+    pub sum:usize
+}
+
+impl HookJson {
+    pub fn new(hook:&Hook) -> HookJson {
+        return HookJson{
+            dim:hook.dim,
+            corner:[hook.position.x, hook.position.y],
+            orientation:format!("{:?}", hook.orientation)
+        };
+    }
+    pub fn to_hook(&self) -> Result<Hook, String> {
+        let orientation = match self.orientation.as_str() {
+            "NW" => HookOrientation::NW,
+            "NE" => HookOrientation::NE,
+            "SW" => HookOrientation::SW,
+            "SE" => HookOrientation::SE,
+            other => return Err(format!("Unknown hook orientation '{}'", other))
+        };
+        return Ok(Hook::new(self.dim, Coord::new(self.corner[0], self.corner[1]), orientation));
+    }
+}
+
+/// Return the hooks of a JSON hook list, checking that they partition the
+/// grid.
+fn hooks_from_json(hooks:&[HookJson], dim:usize) -> Result<Vec<Hook>, String> {
+    let hooks = hooks.iter().map(HookJson::to_hook).collect::<Result<Vec<Hook>, String>>()?;
+    verify_hooks(dim, &hooks)?;
+    return Ok(hooks);
+}
+
+impl SolutionJson {
+    pub fn new(solution:&HooksSolution) -> SolutionJson {
+        let hooks = solution.hooks.iter().map(HookJson::new).collect();
+        let shapes = solution.shapes;
+        let pentominos = solution.pentominos.iter().zip(solution.sums.iter()).map(|(pentomino, (_, sum))| PentominoJson{
+            class:shapes.class(pentomino).to_string(),
+            orientation:shapes.orientation(pentomino).rows.clone(),
+            position:[pentomino.x as isize, pentomino.y as isize],
+            sum:*sum
+        }).collect();
+        return SolutionJson{
+            hooks:hooks,
+            assignment:solution.assignment.clone(),
+            pentominos:pentominos,
+            digits:solution.digits.clone(),
+            empty_regions:solution.empty_regions.clone(),
+            product:solution.answer
+        };
+    }
+    /// Rebuild and verify the solution, checking that the recorded digits,
+    /// sums, empty regions and product match the hooks and pentominos.
+    pub fn to_solution(&self, puzzle:&Puzzle) -> Result<HooksSolution, String> {
+        let hooks = self.hooks.iter().map(HookJson::to_hook).collect::<Result<Vec<Hook>, String>>()?;
+        let shapes = puzzle.shapes;
+        let classes = grid::pentomino_classes(puzzle);
+        let mut pentominos:Vec<Placement> = Vec::new();
+        for pentomino in self.pentominos.iter() {
+            let class = match shapes.shape_id(&pentomino.class) {
+                Some(class) if classes.contains(&class) => class,
+                _ => return Err(format!("Unknown pentomino class '{}'", pentomino.class))
+            };
+            let position = Coord::new(pentomino.position[0], pentomino.position[1]);
+            match shapes.find_orientation(class, &parse_shape(&pentomino.orientation.join("\n"))) {
+                Some(orientation) => match shapes.placement(orientation, &position) {
+                    Some(placement) => pentominos.push(placement),
+                    None => return Err(format!("Pentomino {} at {} leaves the grid", pentomino.class, position))
+                },
+                None => return Err(format!("Pentomino {} has an orientation {:?} that is not of its class", pentomino.class, pentomino.orientation))
+            }
+        }
+        let unchecked = HooksSolution{hooks:hooks, assignment:self.assignment.clone(), pentominos:pentominos, shapes:shapes, digits:Vec::new(), sums:Vec::new(), empty_regions:Vec::new(), answer:0};
+        verify_solution(puzzle, &unchecked)?;
+        let solution = HooksSolution::new(puzzle, &unchecked.hooks, &unchecked.assignment, &unchecked.pentominos);
+        if self.digits != solution.digits {
+            return Err("Recorded digits do not match the hooks and pentominos".to_string());
+        }
+        for (pentomino, (class, sum)) in self.pentominos.iter().zip(solution.sums.iter()) {
+            if pentomino.sum != *sum {
+                return Err(format!("Recorded sum {} of pentomino {} should be {}", pentomino.sum, class, sum));
+            }
+        }
+        if self.empty_regions != solution.empty_regions {
+            return Err(format!("Recorded empty regions {:?} should be {:?}", self.empty_regions, solution.empty_regions));
+        }
+        if self.product != solution.answer {
+            return Err(format!("Recorded product {} should be {}", self.product, solution.answer));
+        }
+        return Ok(solution);
+    }
+}
+
+/// Return the JSON form of a list of solutions.
+pub fn solutions_to_json(puzzle:&Puzzle, solutions:&[HooksSolution]) -> String {
+    return solutions_with_stats_to_json(puzzle, solutions, None);
+}
+
+/// Return the JSON form of a list of solutions, with the statistics of the
+/// search that found them if given.
+pub fn solutions_with_stats_to_json(puzzle:&Puzzle, solutions:&[HooksSolution], stats:Option<&SearchStats>) -> String {
+    let json = SolutionsJson{dim:puzzle.dim, solutions:solutions.iter().map(SolutionJson::new).collect(), stats:stats.map(SearchStats::to_json)};
+    return serde_json::to_string_pretty(&json).unwrap();
+}
+
+/// Read solutions written by `solutions_to_json` and verify each against the
+/// puzzle.
+pub fn solutions_from_json(puzzle:&Puzzle, input:&str) -> Result<Vec<HooksSolution>, String> {
+    let json:SolutionsJson = match serde_json::from_str(input) {
+        Ok(json) => json,
+        Err(e) => return Err(format!("Could not parse solutions: {}", e))
+    };
+    if json.dim != puzzle.dim {
+        return Err(format!("Solutions are for a {}x{} grid but the puzzle is {}x{}", json.dim, json.dim, puzzle.dim, puzzle.dim));
+    }
+    let mut solutions:Vec<HooksSolution> = Vec::new();
+    for (i, solution) in json.solutions.iter().enumerate() {
+        match solution.to_solution(puzzle) {
+            Ok(solution) => solutions.push(solution),
+            Err(e) => return Err(format!("Solution {}: {}", i + 1, e))
+        }
+    }
+    return Ok(solutions);
+}
+
+/// Return the JSON form of a list of hook arrangements.
+pub fn hook_arrangements_to_json(puzzle:&Puzzle, arrangements:&[Vec<Hook>]) -> String {
+    let json = HookArrangementsJson{dim:puzzle.dim, arrangements:arrangements.iter().map(|hooks| hooks.iter().map(HookJson::new).collect()).collect()};
+    return serde_json::to_string_pretty(&json).unwrap();
+}
+
+/// Read hook arrangements written by `hook_arrangements_to_json`, checking
+/// that each partitions the puzzle's grid.
+pub fn hook_arrangements_from_json(puzzle:&Puzzle, input:&str) -> Result<Vec<Vec<Hook>>, String> {
+    let json:HookArrangementsJson = match serde_json::from_str(input) {
+        Ok(json) => json,
+        Err(e) => return Err(format!("Could not parse hook arrangements: {}", e))
+    };
+    if json.dim != puzzle.dim {
+        return Err(format!("Hook arrangements are for a {}x{} grid but the puzzle is {}x{}", json.dim, json.dim, puzzle.dim, puzzle.dim));
+    }
+    let mut arrangements:Vec<Vec<Hook>> = Vec::new();
+    for (i, hooks) in json.arrangements.iter().enumerate() {
+        match hooks_from_json(hooks, puzzle.dim) {
+            Ok(hooks) => arrangements.push(hooks),
+            Err(e) => return Err(format!("Arrangement {}: {}", i + 1, e))
+        }
+    }
+    return Ok(arrangements);
+}
+
+/// Return the JSON form of a list of hook/number combinations.
+pub fn combinations_to_json(puzzle:&Puzzle, combinations:&[Combination]) -> String {
+    let json = CombinationsJson{dim:puzzle.dim, combinations:combinations.iter().map(|(hooks, assignment)| CombinationJson{
+        hooks:hooks.iter().map(HookJson::new).collect(),
+        assignment:assignment.clone()
+    }).collect()};
+    return serde_json::to_string_pretty(&json).unwrap();
+}
+
+/// Read hook/number combinations written by `combinations_to_json`, checking
+/// that the hooks partition the puzzle's grid and that each number is
+/// assigned to one hook.
+pub fn combinations_from_json(puzzle:&Puzzle, input:&str) -> Result<Vec<Combination>, String> {
+    let json:CombinationsJson = match serde_json::from_str(input) {
+        Ok(json) => json,
+        Err(e) => return Err(format!("Could not parse combinations: {}", e))
+    };
+    if json.dim != puzzle.dim {
+        return Err(format!("Combinations are for a {}x{} grid but the puzzle is {}x{}", json.dim, json.dim, puzzle.dim, puzzle.dim));
+    }
+    let mut combinations:Vec<Combination> = Vec::new();
+    for (i, combination) in json.combinations.iter().enumerate() {
+        let checked = hooks_from_json(&combination.hooks, puzzle.dim).and_then(|hooks| {
+            verify_assignment(puzzle.dim, &combination.assignment)?;
+            return Ok(hooks);
+        });
+        match checked {
+            Ok(hooks) => combinations.push((hooks, combination.assignment.clone())),
+            Err(e) => return Err(format!("Combination {}: {}", i + 1, e))
+        }
+    }
+    return Ok(combinations);
+}
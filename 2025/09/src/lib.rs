@@ -0,0 +1,243 @@
+#![allow(clippy::needless_return, clippy::needless_range_loop, clippy::redundant_field_names)]
+//! Solver for the Jane Street September 2025 puzzle (Hooks 11) and other
+//! Hooks puzzles described in the same format.
+//!
+//! The search runs in three stages: `enumerate_hook_arrangements` finds the
+//! hook layouts allowed by the puzzle, `enumerate_number_assignments` assigns
+//! numbers to the hooks of each layout, and `solve_pentominos` searches each
+//! hook/number combination for a valid pentomino layout.
+pub mod bitboard;
+pub mod board;
+pub mod cells;
+pub mod checkpoint;
+pub mod constraint;
+pub mod dlx;
+pub mod generate;
+pub mod grid;
+pub mod json;
+pub mod polyomino;
+pub mod pool;
+pub mod puzzle;
+pub mod sat;
+pub mod shape;
+pub mod stats;
+pub mod tree;
+pub mod verify;
+
+pub use checkpoint::Checkpoint;
+pub use grid::{Coord, Hook, HookOrientation, Engine, SearchOptions, Strategy};
+pub use puzzle::Puzzle;
+pub use shape::{Placement, ShapeTable};
+pub use stats::SearchStats;
+pub use tree::{SearchTree, TreeNode};
+
+/// A solution to a Hooks puzzle.
+#[derive(Debug, Clone)]
+pub struct HooksSolution {
+    pub hooks:Vec<Hook>,
+    /// Number assigned to each hook, in the same order as `hooks`
+    pub assignment:Vec<usize>,
+    pub pentominos:Vec<Placement>,
+    /// Table the placements in `pentominos` index into
+    pub shapes:&'static ShapeTable,
+    /// Digit in each cell, indexed `[y][x]`, or `None` for an empty cell
+    pub digits:Vec<Vec<Option<usize>>>,
+    /// Sum of the digits within each pentomino, in the same order as
+    /// `pentominos`
+    pub sums:Vec<(String, usize)>,
+    /// Areas of the empty regions of the grid, in row-major order of their
+    /// first cell
+    pub empty_regions:Vec<usize>,
+    /// Product of the areas of the empty regions of the grid
+    pub answer:usize
+}
+impl HooksSolution {
+    /// Build a solution from its hooks, number assignment and pentominos.
+    pub fn new(puzzle:&Puzzle, hooks:&[Hook], assignment:&[usize], pentominos:&[Placement]) -> HooksSolution {
+        let dim = puzzle.dim;
+        let shapes = puzzle.shapes;
+        let masks:Vec<bitboard::Bitboard> = hooks.iter().map(|hook| hook.mask(dim)).collect();
+        let digit_at = |coord:&Coord| -> usize {
+            let i = masks.iter().position(|mask| mask.contains(coord, dim)).unwrap();
+            return assignment[i];
+        };
+        let mut digits = vec![vec![None; dim]; dim];
+        for pentomino in pentominos.iter() {
+            for coord in shapes.mask(pentomino).coords(dim) {
+                digits[coord.y as usize][coord.x as usize] = Some(digit_at(&coord));
+            }
+        }
+        let empty_regions = grid::pentominos_empty_regions(shapes, pentominos);
+        let sums = pentominos.iter().map(|p| (shapes.class(p).to_string(), shapes.mask(p).coords(dim).iter().map(digit_at).sum())).collect();
+        return HooksSolution{
+            hooks:hooks.to_vec(),
+            assignment:assignment.to_vec(),
+            pentominos:pentominos.to_vec(),
+            shapes:shapes,
+            digits:digits,
+            sums:sums,
+            answer:empty_regions.iter().product(),
+            empty_regions:empty_regions
+        };
+    }
+    /// Print the hooks, number assignment, pentominos, digits, pentomino sums
+    /// and answer.
+    pub fn print(&self) {
+        let dim = self.digits.len();
+        println!("\nHooks:");
+        grid::hook_print(&self.hooks, dim);
+        println!("\nNumber assignments to hooks:");
+        for (hook, number) in self.hooks.iter().zip(self.assignment.iter()) {
+            println!("{}x{} <- {}", hook.dim, hook.dim, number);
+        }
+        println!("\nPentominos:");
+        grid::pentominos_print(self.shapes, &self.pentominos);
+        println!("\nDigits:");
+        for row in self.digits.iter() {
+            for digit in row.iter() {
+                match digit {
+                    Some(d) => print!("{} ", d),
+                    None => print!(". ")
+                }
+            }
+            println!();
+        }
+        println!("\nPentomino sums:");
+        for (class, sum) in self.sums.iter() {
+            println!("{}: {}", class, sum);
+        }
+        println!("\nProduct of empty region areas: {}", self.answer);
+    }
+}
+
+/// Check that a solution's hooks partition the grid, that its assignment
+/// gives each hook a distinct number, and that its pentominos satisfy every
+/// rule and clue of the puzzle. Returns a description of the first problem
+/// found.
+pub fn verify_solution(puzzle:&Puzzle, solution:&HooksSolution) -> Result<(), String> {
+    let dim = puzzle.dim;
+    if solution.hooks.len() != dim || solution.assignment.len() != dim {
+        return Err(format!("Expected {} hooks and numbers, found {} hooks and {} numbers", dim, solution.hooks.len(), solution.assignment.len()));
+    }
+    verify_hooks(dim, &solution.hooks)?;
+    verify_assignment(dim, &solution.assignment)?;
+    if !std::ptr::eq(solution.shapes, puzzle.shapes) {
+        return Err("The pentominos are placed on a different grid or with another piece size".to_string());
+    }
+    if solution.pentominos.len() != puzzle.piece_count() {
+        return Err(format!("Expected {} pentominos, found {}", puzzle.piece_count(), solution.pentominos.len()));
+    }
+    let digit_masks = grid::digit_masks(&solution.hooks, &solution.assignment, dim);
+    if !grid::pentomino_validate(puzzle, &solution.pentominos, &digit_masks) {
+        return Err("The pentominos do not satisfy the puzzle rules and clues".to_string());
+    }
+    return Ok(());
+}
+
+/// Check that hooks of sizes 1 to `dim`, one of each size, partition a
+/// `dim`x`dim` grid.
+pub fn verify_hooks(dim:usize, hooks:&[Hook]) -> Result<(), String> {
+    if hooks.len() != dim {
+        return Err(format!("Expected {} hooks, found {}", dim, hooks.len()));
+    }
+    let mut covered = bitboard::Bitboard::EMPTY;
+    for hook in hooks.iter() {
+        if hook.dim == 0 || hook.dim > dim || hooks.iter().filter(|h| h.dim == hook.dim).count() != 1 {
+            return Err(format!("Hook sizes must be 1 to {} with one hook of each size", dim));
+        }
+        let mask = match bitboard::Bitboard::from_coords(hook.cells().iter(), dim) {
+            Some(mask) => mask,
+            None => return Err(format!("The {}x{} hook at {} leaves the grid", hook.dim, hook.dim, hook.position))
+        };
+        if mask.intersects(&covered) {
+            return Err(format!("The {}x{} hook at {} overlaps another hook", hook.dim, hook.dim, hook.position));
+        }
+        covered |= mask;
+    }
+    return Ok(());
+}
+
+/// Check that an assignment gives each number 1 to `dim` to exactly one hook.
+pub fn verify_assignment(dim:usize, assignment:&[usize]) -> Result<(), String> {
+    if assignment.len() != dim {
+        return Err(format!("Expected {} numbers, found {}", dim, assignment.len()));
+    }
+    for d in 1..=dim {
+        if assignment.iter().filter(|n| **n == d).count() != 1 {
+            return Err(format!("Number {} is not assigned to exactly one hook", d));
+        }
+    }
+    return Ok(());
+}
+
+/// Return every hook arrangement that does not contradict the puzzle's given
+/// 1s and 2s or its 1 and 2 edge clues.
+pub fn enumerate_hook_arrangements(puzzle:&Puzzle) -> Vec<Vec<Hook>> {
+    return enumerate_hook_arrangements_with_tree(puzzle, None).0;
+}
+
+/// Enumerate hook arrangements as `enumerate_hook_arrangements` does, also
+/// returning the tree explored down to `tree_depth` hooks if it is set.
+pub fn enumerate_hook_arrangements_with_tree(puzzle:&Puzzle, tree_depth:Option<usize>) -> (Vec<Vec<Hook>>, Option<TreeNode>) {
+    let mut hook_arrangements:Vec<Vec<Hook>> = Vec::new();
+    let mut tree = tree::TreeRecorder::new("hooks", tree_depth);
+    grid::hook_recurse(puzzle, puzzle.dim, &mut Vec::new(), Coord::new(0,0), &mut hook_arrangements, &mut tree);
+    return (hook_arrangements, tree.finish().map(|(_, node)| node));
+}
+
+/// Return every combination of a hook arrangement and an assignment of
+/// numbers to its hooks that is consistent with the given digits and the
+/// digit edge clues.
+pub fn enumerate_number_assignments(puzzle:&Puzzle, hook_arrangements:&[Vec<Hook>]) -> Vec<(Vec<Hook>, Vec<usize>)> {
+    return enumerate_number_assignments_with_tree(puzzle, hook_arrangements, None).0;
+}
+
+/// Enumerate hook/number combinations as `enumerate_number_assignments`
+/// does, also returning the tree explored down to `tree_depth` levels below
+/// the arrangements if it is set.
+pub fn enumerate_number_assignments_with_tree(puzzle:&Puzzle, hook_arrangements:&[Vec<Hook>], tree_depth:Option<usize>) -> (Vec<json::Combination>, Option<TreeNode>) {
+    let mut hook_number_assignments:Vec<json::Combination> = Vec::new();
+    let mut tree = tree::TreeRecorder::new("assign", tree_depth.map(|depth| depth + 1));
+    for (i, hooks) in hook_arrangements.iter().enumerate() {
+        let mut assignments:Vec<Vec<usize>> = Vec::new();
+        tree.enter(|| format!("arrangement {}", i));
+        grid::hook_number_assignment_recurse(puzzle, hooks, &mut vec![0; puzzle.dim], 0, &mut assignments, &mut tree);
+        tree.leave();
+        for assignment in assignments {
+            hook_number_assignments.push((hooks.clone(), assignment));
+        }
+    }
+    return (hook_number_assignments, tree.finish().map(|(_, node)| node));
+}
+
+/// Search the hook/number combinations for pentomino layouts that solve the
+/// puzzle, as configured by `options`. Unless `options.all` is set, only the
+/// first combination with solutions is searched to completion. `progress` is
+/// called with the number of search tasks completed and created so far.
+///
+/// Returns an error if a piece clue names a class that cannot be placed on the
+/// grid, or if there are fewer classes that can be placed than pieces.
+pub fn solve_pentominos(puzzle:&Puzzle, hook_number_assignments:&[(Vec<Hook>, Vec<usize>)], options:&SearchOptions, progress:&(dyn Fn(usize, usize) + Sync)) -> Result<Vec<HooksSolution>, String> {
+    return solve_pentominos_with_stats(puzzle, hook_number_assignments, options, progress).map(|(solutions, _)| solutions);
+}
+
+/// Search the hook/number combinations as `solve_pentominos` does, also
+/// returning the nodes visited and placements rejected by the search.
+pub fn solve_pentominos_with_stats(puzzle:&Puzzle, hook_number_assignments:&[(Vec<Hook>, Vec<usize>)], options:&SearchOptions, progress:&(dyn Fn(usize, usize) + Sync)) -> Result<(Vec<HooksSolution>, SearchStats), String> {
+    let classes = grid::pentomino_classes(puzzle);
+    let noun = polyomino::piece_noun(puzzle.piece_size);
+    for (clue, class) in puzzle.pentomino_clues() {
+        if !classes.iter().any(|c| puzzle.shapes.name(*c) == class) {
+            return Err(format!("Clue {} does not name a {} that can be placed on the grid", clue, noun));
+        }
+    }
+    if classes.len() < puzzle.piece_count() {
+        return Err(format!("The filled cells must be divided into {} distinct {}s, but only {} can be placed on the grid", puzzle.piece_count(), noun, classes.len()));
+    }
+    let (solutions, stats) = grid::pentomino_search(puzzle, &classes, hook_number_assignments, options, progress);
+    let solutions = solutions.iter().map(|(i, pentominos)| {
+        let (hooks, assignment) = &hook_number_assignments[*i];
+        HooksSolution::new(puzzle, hooks, assignment, pentominos)
+    }).collect();
+    return Ok((solutions, stats));
+}
@@ -1,14 +1,43 @@
-mod grid;
-use grid::{Hook, Coord};
-use std::time::{Instant};
-use clap::{Arg, Command};
+#![allow(clippy::needless_return, clippy::needless_range_loop, clippy::redundant_field_names)]
+use sept2025::json::Combination;
+use sept2025::puzzle::{self, Puzzle};
+use sept2025::{generate, grid, polyomino, verify, Checkpoint, Engine, SearchOptions, SearchTree, Strategy};
+use sept2025::sat::HooksCnf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use clap::{Arg, ArgAction, ArgMatches, Command};
 use indicatif::{ProgressBar,ProgressStyle};
 
 fn main() {
-	let command = Command::new("sept2025").max_term_width(80)
+	let command = search_args(Command::new("sept2025").max_term_width(80))
         .about("Solver for the Jane Street September 2025 puzzle.")
-        .arg(Arg::new("threads").help("Set maximum number of worker threads").short('t').long("threads").value_name("THREADS").default_value("4"));
+        .arg(Arg::new("threads").help("Set maximum number of worker threads").short('t').long("threads").value_name("THREADS").default_value("4").global(true))
+        .arg(Arg::new("puzzle").help("Solve the puzzle in the given puzzle description file instead of the September 2025 puzzle").short('p').long("puzzle").value_name("FILE").global(true))
+        .arg(Arg::new("check").help("Verify the solutions in a file written with --format json against the puzzle instead of solving it").long("check").value_name("FILE"))
+        .arg(Arg::new("emit-dimacs").help("Write the puzzle as a SAT instance in DIMACS CNF format, with the variable names in FILE.map, instead of solving it").long("emit-dimacs").value_name("FILE"))
+        .arg(Arg::new("read-model").help("Decode and verify a SAT solver's model of the instance written by --emit-dimacs instead of solving").long("read-model").value_name("FILE"))
+        .subcommand(Command::new("generate")
+            .about("Generate a random uniquely solvable puzzle with no removable clues")
+            .arg(Arg::new("size").help("Set the grid size").long("size").value_name("N").default_value("9"))
+            .arg(Arg::new("seed").help("Set the random seed, chosen from the clock by default").long("seed").value_name("SEED"))
+            .arg(Arg::new("time-limit").help("Set the number of seconds allowed for proving the puzzle unique without a clue, after which the clue is kept").long("time-limit").value_name("SECONDS").default_value("60"))
+            .arg(Arg::new("output").help("Write the puzzle description to a file instead of standard output").short('o').long("output").value_name("FILE")))
+        .subcommand(Command::new("verify")
+            .about("Check a proposed solution grid against every rule of the puzzle, exiting with status 1 if any is broken")
+            .arg(Arg::new("grid").help("File with the digit grid, using '.' for empty cells").value_name("GRID").required(true))
+            .arg(Arg::new("hooks").help("File with the hook layout, giving the size of the hook of each cell, instead of searching for one").long("hooks").value_name("FILE")))
+        .subcommand(Command::new("hooks")
+            .about("Find the hook arrangements allowed by the puzzle and write them as JSON")
+            .arg(Arg::new("output").help("Write the hook arrangements to a file instead of standard output").short('o').long("output").value_name("FILE")))
+        .subcommand(Command::new("assign")
+            .about("Find the hook/number combinations allowed by the puzzle and write them as JSON")
+            .arg(Arg::new("arrangements").help("File with the hook arrangements written by the hooks command, instead of finding them").value_name("HOOKS"))
+            .arg(Arg::new("output").help("Write the combinations to a file instead of standard output").short('o').long("output").value_name("FILE")))
+        .subcommand(search_args(Command::new("pentominos"))
+            .about("Search hook/number combinations for pentomino layouts that solve the puzzle")
+            .arg(Arg::new("combinations").help("File with the combinations written by the assign command, instead of finding them").value_name("COMBINATIONS"))
+            .arg(Arg::new("output").help("Also write the solutions to a file in the form given by --format json").short('o').long("output").value_name("FILE")));
     let args = command.get_matches();    
+    let json = args.get_one::<String>("format").unwrap() == "json";
     let mut threads = 4;
     if let Some(threads_arg) = args.get_one::<String>("threads") {
         match threads_arg.parse::<usize>() {
@@ -25,89 +54,451 @@ fn main() {
             }
         }
     }
-    println!("####### Jane Street Puzzle - September 2025 #######\n");
+    if let Some(generate_args) = args.subcommand_matches("generate") {
+        generate(generate_args, threads);
+        return;
+    }
+    let puzzle = match args.get_one::<String>("puzzle") {
+        Some(path) => Puzzle::from_file(path),
+        None => Puzzle::parse(puzzle::SEPTEMBER_PUZZLE)
+    };
+    let puzzle = match puzzle {
+        Ok(puzzle) => puzzle,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    if let Some(verify_args) = args.subcommand_matches("verify") {
+        verify(&puzzle, verify_args);
+        return;
+    }
+    if let Some(hooks_args) = args.subcommand_matches("hooks") {
+        hooks(&puzzle, hooks_args);
+        return;
+    }
+    if let Some(assign_args) = args.subcommand_matches("assign") {
+        assign(&puzzle, assign_args);
+        return;
+    }
+    if let Some(pentominos_args) = args.subcommand_matches("pentominos") {
+        pentominos(&puzzle, pentominos_args, threads);
+        return;
+    }
+    if let Some(path) = args.get_one::<String>("emit-dimacs") {
+        let encoding = HooksCnf::new(&puzzle, &grid::pentomino_classes(&puzzle));
+        if let Err(e) = encoding.write_dimacs(path) {
+            println!("{}", e);
+            std::process::exit(2);
+        }
+        println!("Wrote {} variables and {} clauses to {}, variable names to {}.map", encoding.cnf.num_vars, encoding.cnf.clauses.len(), path, path);
+        return;
+    }
+    if let Some(path) = args.get_one::<String>("read-model") {
+        read_model(&puzzle, path, json);
+        return;
+    }
+    if let Some(path) = args.get_one::<String>("check") {
+        check(&puzzle, path, json);
+        return;
+    }
+    solve(&puzzle, &args, threads, None, None);
+}
+
+/// Add the options of the pentomino search to a command.
+fn search_args(command:Command) -> Command {
+    return command
+        .arg(Arg::new("all").help("Search every hook/number combination to completion and report all solutions, exiting with status 3 unless exactly one is found").long("all").action(ArgAction::SetTrue))
+        .arg(Arg::new("first").help("Stop at the first solution found, in any hook/number combination").long("first").action(ArgAction::SetTrue).conflicts_with_all(["all", "unique"]))
+        .arg(Arg::new("unique").help("Check that the puzzle has exactly one solution, stopping the search at the second, and exit with status 3 otherwise").long("unique").action(ArgAction::SetTrue).conflicts_with("all"))
+        .arg(Arg::new("format").help("Set the output format").long("format").value_name("FORMAT").value_parser(["text", "json"]).default_value("text"))
+        .arg(Arg::new("stats").help("Report the nodes visited per depth and per pentomino class, and the placements rejected by each rule per hook/number combination").long("stats").action(ArgAction::SetTrue))
+        .arg(Arg::new("strategy").help("Set how each hook/number combination is searched: by placing pentominos, or by deciding which cells are filled with constraint propagation and dividing complete fillings into pentominos").long("strategy").value_name("STRATEGY").value_parser(["pentominos", "cells"]).default_value("pentominos"))
+        .arg(Arg::new("engine").help("Set the search backend for placing pentominos: depth first search or exact cover with dancing links").long("engine").value_name("ENGINE").value_parser(["dfs", "dlx"]).default_value("dfs"))
+        .arg(Arg::new("checkpoint").help("Periodically write the search progress to a checkpoint file").long("checkpoint").value_name("FILE"))
+        .arg(Arg::new("checkpoint-interval").help("Set the number of seconds between checkpoints").long("checkpoint-interval").value_name("SECONDS").default_value("60"))
+        .arg(Arg::new("resume").help("Continue the search from a checkpoint file, which is updated as the search proceeds unless --checkpoint is given").long("resume").value_name("FILE"))
+        .arg(Arg::new("tree").help("Write the tree explored by each search stage to a file, with the nodes, time, solutions and pruning rules of every subtree").long("tree").value_name("FILE"))
+        .arg(Arg::new("tree-depth").help("Set the number of levels of each stage recorded as separate nodes in the tree, below the arrangements and combinations; deeper levels are aggregated into their ancestors").long("tree-depth").value_name("DEPTH").default_value("3"))
+        .arg(Arg::new("tree-format").help("Set the format of the tree file").long("tree-format").value_name("FORMAT").value_parser(["json", "dot"]).default_value("json"))
+        .arg(Arg::new("report").help("Write a report with a row per hook/number combination giving its hook layout hash, assignment, prefix tasks, nodes visited, wall and CPU time and solutions found").long("report").value_name("FILE"))
+        .arg(Arg::new("report-format").help("Set the format of the report file").long("report-format").value_name("FORMAT").value_parser(["csv", "json"]).default_value("csv"));
+}
+
+/// Read a file named on the command line, exiting with status 2 if it cannot
+/// be read.
+fn read_input(path:&str, what:&str) -> String {
+    return match std::fs::read_to_string(path) {
+        Ok(input) => input,
+        Err(e) => {
+            println!("Could not read {} file '{}': {}", what, path, e);
+            std::process::exit(2);
+        }
+    };
+}
+
+/// Write the output of a stage to the file given by `-o`, or to standard
+/// output.
+fn write_output(args:&ArgMatches, output:&str, what:&str) {
+    match args.get_one::<String>("output") {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, output) {
+                println!("Could not write {} file '{}': {}", what, path, e);
+                std::process::exit(2);
+            }
+        },
+        None => println!("{}", output)
+    }
+}
+
+/// Find the hook arrangements and write them, reporting the count on
+/// standard error.
+fn hooks(puzzle:&Puzzle, args:&ArgMatches) {
+    let start_instant = Instant::now();
+    let hook_arrangements = sept2025::enumerate_hook_arrangements(puzzle);
+    write_output(args, &sept2025::json::hook_arrangements_to_json(puzzle, &hook_arrangements), "hook arrangements");
+    eprintln!("Found {} hook arrangements in {:?}", hook_arrangements.len(), start_instant.elapsed());
+}
+
+/// Find the hook/number combinations for the hook arrangements read from a
+/// file, or for every arrangement, and write them, reporting the count on
+/// standard error.
+fn assign(puzzle:&Puzzle, args:&ArgMatches) {
+    let start_instant = Instant::now();
+    let hook_arrangements = match args.get_one::<String>("arrangements") {
+        Some(path) => match sept2025::json::hook_arrangements_from_json(puzzle, &read_input(path, "hook arrangements")) {
+            Ok(hook_arrangements) => hook_arrangements,
+            Err(e) => {
+                println!("Invalid hook arrangements file {}: {}", path, e);
+                std::process::exit(2);
+            }
+        },
+        None => sept2025::enumerate_hook_arrangements(puzzle)
+    };
+    let hook_number_assignments = sept2025::enumerate_number_assignments(puzzle, &hook_arrangements);
+    write_output(args, &sept2025::json::combinations_to_json(puzzle, &hook_number_assignments), "combinations");
+    eprintln!("Found {} hook/number combinations for {} hook arrangements in {:?}", hook_number_assignments.len(), hook_arrangements.len(), start_instant.elapsed());
+}
+
+/// Search the hook/number combinations read from a file, or every
+/// combination, for pentomino layouts.
+fn pentominos(puzzle:&Puzzle, args:&ArgMatches, threads:usize) {
+    let hook_number_assignments = args.get_one::<String>("combinations").map(|path| match sept2025::json::combinations_from_json(puzzle, &read_input(path, "combinations")) {
+        Ok(hook_number_assignments) => hook_number_assignments,
+        Err(e) => {
+            println!("Invalid combinations file {}: {}", path, e);
+            std::process::exit(2);
+        }
+    });
+    solve(puzzle, args, threads, hook_number_assignments, args.get_one::<String>("output"));
+}
+
+/// Search for solutions with the search options in `args` and print them,
+/// finding the hook/number combinations first unless they are given, and
+/// also write them as JSON to `output` if given.
+fn solve(puzzle:&Puzzle, args:&ArgMatches, threads:usize, hook_number_assignments:Option<Vec<Combination>>, output:Option<&String>) {
+    let all = args.get_flag("all");
+    let first = args.get_flag("first");
+    let unique = args.get_flag("unique");
+    let show_stats = args.get_flag("stats");
+    let json = args.get_one::<String>("format").unwrap() == "json";
+    let strategy = if args.get_one::<String>("strategy").unwrap() == "cells" { Strategy::Cells } else { Strategy::Pentominos };
+    let engine = if args.get_one::<String>("engine").unwrap() == "dlx" { Engine::Dlx } else { Engine::Dfs };
+    let mut checkpoint_interval = 60;
+    if let Some(interval_arg) = args.get_one::<String>("checkpoint-interval") {
+        match interval_arg.parse::<u64>() {
+            Ok(n) => checkpoint_interval = n,
+            Err(_) => {
+                println!("Could not parse SECONDS argument '{}' as an integer.", interval_arg);
+                std::process::exit(2);
+            }
+        }
+    }
+    let tree_path = args.get_one::<String>("tree");
+    let mut tree_depth = None;
+    if tree_path.is_some() {
+        let depth_arg = args.get_one::<String>("tree-depth").unwrap();
+        match depth_arg.parse::<usize>() {
+            Ok(n) => tree_depth = Some(n),
+            Err(_) => {
+                println!("Could not parse DEPTH argument '{}' as an integer.", depth_arg);
+                std::process::exit(2);
+            }
+        }
+        if strategy != Strategy::Pentominos || engine != Engine::Dfs {
+            println!("--tree records the pentominos strategy with the dfs engine only");
+            std::process::exit(2);
+        }
+    }
+    if !json {
+        println!("####### Jane Street Puzzle - September 2025 #######\n");
+    }
 	let start_instant = Instant::now();
-	// Initialize pentomino classes
-    let pentomino_map = grid::init_pentominos();
-	// Find all potentially valid hook arrangements
-    let mut hook_arrangements:Vec<Vec<Hook>> = Vec::new();
-    grid::hook_recurse(9, &mut Vec::new(), Coord::new(0,0), &mut hook_arrangements);
-	// Find all potentially valid assignments of numbers to valid hooks
-    let mut hook_number_assignments:Vec<(Vec<Hook>, Vec<usize>)> = Vec::new();
-	for i in 0..hook_arrangements.len() {
-		let mut solutions:Vec<Vec<usize>> = Vec::new();
-		let mut initial_assignment = vec![0; grid::GRID_DIM];
-		grid::hook_number_assignment_recurse(&hook_arrangements[i], &mut initial_assignment, 0, &mut solutions);
-		for j in 0..solutions.len() {
-			hook_number_assignments.push((hook_arrangements[i].clone(), solutions[j].clone()));
-		}
-	}
-	println!("Searching for pentomino positions within {} potential number/hook combinations with {} worker threads...", hook_number_assignments.len(), threads);	
-	let bar = ProgressBar::new(hook_number_assignments.len() as u64);
-	bar.set_style(ProgressStyle::with_template("[{elapsed_precise}] {bar:40} {pos:>7}/{len:7} {percent}%").unwrap());
+    let mut tree = SearchTree::default();
+    let hook_number_assignments = hook_number_assignments.unwrap_or_else(|| {
+	    // Find all potentially valid hook arrangements
+        let (hook_arrangements, hooks_tree) = sept2025::enumerate_hook_arrangements_with_tree(puzzle, tree_depth);
+	    // Find all potentially valid assignments of numbers to valid hooks
+        let (hook_number_assignments, assign_tree) = sept2025::enumerate_number_assignments_with_tree(puzzle, &hook_arrangements, tree_depth);
+        tree.hooks = hooks_tree;
+        tree.assign = assign_tree;
+        return hook_number_assignments;
+    });
+	let bar = if json { ProgressBar::hidden() } else { ProgressBar::new(hook_number_assignments.len() as u64) };
+    if !json {
+	    println!("Searching for {} positions within {} potential number/hook combinations with {} worker threads...", polyomino::piece_noun(puzzle.piece_size), hook_number_assignments.len(), threads);	
+    }
+	bar.set_style(ProgressStyle::with_template("[{elapsed_precise}] {bar:40} {pos:>7}/{len:7} tasks {percent}%").unwrap());
     bar.inc(0);
-    for i in 0..hook_number_assignments.len() {
-        let pentomino_solutions = grid::pentomino_permutations(&pentomino_map, &hook_number_assignments, i, threads);
-		bar.inc(1);
-        if pentomino_solutions.len() > 0 {
-            bar.finish_and_clear();
-            if pentomino_solutions.len() != 1 {
-                println!("Multiple solutions found, something went wrong: {:?}", pentomino_solutions);
-                break;
+    let mut options = SearchOptions{threads:threads, strategy:strategy, engine:engine, all:all || first || unique, checkpoint_interval:Duration::from_secs(checkpoint_interval), tree_depth:tree_depth, ..Default::default()};
+    if first {
+        options.max_solutions = Some(1);
+    }
+    else if unique {
+        options.max_solutions = Some(2);
+    }
+    options.checkpoint_path = args.get_one::<String>("checkpoint").cloned();
+    if let Some(path) = args.get_one::<String>("resume") {
+        match Checkpoint::from_file(path, puzzle, &hook_number_assignments) {
+            Ok(checkpoint) => {
+                if !json {
+                    println!("Resuming from {} with {} of {} combinations complete and {} solutions found", path, checkpoint.completed_combinations.len(), checkpoint.combinations, checkpoint.solutions.len());
+                }
+                options.resume = Some(checkpoint);
+            },
+            Err(e) => {
+                println!("{}", e);
+                std::process::exit(2);
             }
-            let pentominos = pentomino_solutions.iter().next().unwrap().clone();
-            println!("\nHooks:");
-            grid::hook_print(&hook_number_assignments[i].0);
-            println!("\nNumber assignments to hooks:");
-            for j in 0..hook_number_assignments[i].0.len() {
-                println!("{}x{} <- {}", hook_number_assignments[i].0[j].dim, hook_number_assignments[i].0[j].dim, hook_number_assignments[i].1[j]);
+        }
+        if options.checkpoint_path.is_none() {
+            options.checkpoint_path = Some(path.clone());
+        }
+    }
+    let solutions = sept2025::solve_pentominos_with_stats(puzzle, &hook_number_assignments, &options, &|completed, total| {
+        bar.set_length(total as u64);
+        bar.set_position(completed as u64);
+    });
+    bar.finish_and_clear();
+    let (solutions, mut stats) = match solutions {
+        Ok(solutions) => solutions,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    if let Some(path) = args.get_one::<String>("report") {
+        let rows = stats.combination_report(&hook_number_assignments);
+        let output = if args.get_one::<String>("report-format").unwrap() == "json" { sept2025::stats::combination_report_to_json(&rows) } else { sept2025::stats::combination_report_to_csv(&rows) };
+        if let Err(e) = std::fs::write(path, output) {
+            println!("Could not write report file '{}': {}", path, e);
+            std::process::exit(2);
+        }
+    }
+    if let Some(path) = tree_path {
+        tree.pentominos = stats.tree.take();
+        let output = if args.get_one::<String>("tree-format").unwrap() == "dot" { tree.to_dot() } else { tree.to_json() };
+        if let Err(e) = std::fs::write(path, output) {
+            println!("Could not write tree file '{}': {}", path, e);
+            std::process::exit(2);
+        }
+    }
+    if let Some(path) = output {
+        let stats = if show_stats { Some(&stats) } else { None };
+        if let Err(e) = std::fs::write(path, sept2025::json::solutions_with_stats_to_json(puzzle, &solutions, stats)) {
+            println!("Could not write solutions file '{}': {}", path, e);
+            std::process::exit(2);
+        }
+    }
+    if json {
+        let stats = if show_stats { Some(&stats) } else { None };
+        println!("{}", sept2025::json::solutions_with_stats_to_json(puzzle, &solutions, stats));
+    }
+    else if all || unique {
+        for (n, solution) in solutions.iter().enumerate() {
+            println!("\n####### Solution {} of {} #######", n + 1, solutions.len());
+            solution.print();
+        }
+        match solutions.len() {
+            0 => println!("\nNo solutions found, the puzzle is not solvable"),
+            1 => println!("\nThe puzzle has a unique solution"),
+            _ if unique => println!("\nA second solution was found, the puzzle is not uniquely solvable"),
+            n => println!("\n{} distinct solutions found, the puzzle is not uniquely solvable", n)
+        }
+    }
+    else if solutions.len() > 1 {
+        println!("Multiple solutions found, something went wrong: {:?}", solutions);
+    }
+    else if let Some(solution) = solutions.first() {
+        solution.print();
+    }
+    if show_stats && !json {
+        stats.print();
+    }
+    if !json {
+	    println!("\nTotal execution time: {:?}", start_instant.elapsed());
+    }
+    if (all || unique) && solutions.len() != 1 {
+        std::process::exit(3);
+    }
+}
+
+/// Generate a puzzle and write its description, reporting progress on
+/// standard error.
+fn generate(args:&ArgMatches, threads:usize) {
+    let size_arg = args.get_one::<String>("size").unwrap();
+    let size = match size_arg.parse::<usize>() {
+        Ok(n) => n,
+        Err(_) => {
+            println!("Could not parse N argument '{}' as an integer.", size_arg);
+            std::process::exit(2);
+        }
+    };
+    let seed = match args.get_one::<String>("seed") {
+        Some(seed_arg) => match seed_arg.parse::<u64>() {
+            Ok(n) => n,
+            Err(_) => {
+                println!("Could not parse SEED argument '{}' as an integer.", seed_arg);
+                std::process::exit(2);
+            }
+        },
+        None => SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+    };
+    let time_limit_arg = args.get_one::<String>("time-limit").unwrap();
+    let time_limit = match time_limit_arg.parse::<u64>() {
+        Ok(n) => Duration::from_secs(n),
+        Err(_) => {
+            println!("Could not parse SECONDS argument '{}' as an integer.", time_limit_arg);
+            std::process::exit(2);
+        }
+    };
+    let start_instant = Instant::now();
+    let mut rng = generate::Rng::new(seed);
+    let generated = match generate::generate(size, &mut rng, threads, time_limit, &|message| eprintln!("{}", message)) {
+        Ok(generated) => generated,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    let description = format!("# Hooks {}x{} generated with seed {}\n# Answer: {}\n{}", size, size, seed, generated.solution.answer, generated.puzzle);
+    match args.get_one::<String>("output") {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, description) {
+                println!("Could not write puzzle file '{}': {}", path, e);
+                std::process::exit(2);
             }
-            println!("\nPentominos:");
-            grid::pentominos_print(&pentominos);
-			
-            println!("\nDigits:");
-            for y in 0..grid::GRID_DIM {
-                for x in 0..grid::GRID_DIM {
-                    let coord = Coord::new(x as isize, y as isize);
-                    let mut found = false;
-                    for pentomino in pentominos.iter() {
-                        if pentomino.get_cells_with_offset().contains(&coord) {
-                            found = true;
-                            break;
-                        }
-                    }
-                    if found {
-                        for j in 0..hook_number_assignments[i].0.len() {
-                            if hook_number_assignments[i].0[j].cells().contains(&coord) {
-                                print!("{} ", hook_number_assignments[i].1[j]);
-                                break;
-                            }
-                        }
-                    }
-                    else {
-                        print!(". ");
-                    }
+        },
+        None => print!("{}", description)
+    }
+    eprintln!("Generated a puzzle with {} givens and {} edge clues in {:?}", generated.puzzle.givens.len(), generated.puzzle.clues.len(), start_instant.elapsed());
+}
+
+/// Check a proposed solution grid against the puzzle rules, printing the hook
+/// layout and pentominos it was checked against and every rule it breaks, and
+/// exiting with status 1 if it breaks any.
+fn verify(puzzle:&Puzzle, args:&ArgMatches) {
+    let read = |path:&String| match std::fs::read_to_string(path) {
+        Ok(input) => input,
+        Err(e) => {
+            println!("Could not read '{}': {}", path, e);
+            std::process::exit(2);
+        }
+    };
+    let grid_path = args.get_one::<String>("grid").unwrap();
+    let digits = match verify::parse_grid(&read(grid_path), puzzle.dim) {
+        Ok(digits) => digits,
+        Err(e) => {
+            println!("{}: {}", grid_path, e);
+            std::process::exit(2);
+        }
+    };
+    let labels = args.get_one::<String>("hooks").map(|path| match verify::parse_hook_layout(&read(path), puzzle.dim) {
+        Ok(labels) => labels,
+        Err(e) => {
+            println!("{}: {}", path, e);
+            std::process::exit(2);
+        }
+    });
+    let report = verify::verify_grid(puzzle, &digits, labels.as_deref());
+    println!("Hooks:");
+    grid::hook_print(&report.hooks, puzzle.dim);
+    if let Some(pentominos) = &report.pentominos {
+        println!("\nPentominos:");
+        for y in 0..puzzle.dim {
+            for x in 0..puzzle.dim {
+                let coord = sept2025::Coord::new(x as isize, y as isize);
+                match pentominos.iter().find(|(_, cells)| cells.contains(&coord)) {
+                    Some((class, _)) => print!("{} ", class),
+                    None => print!(". ")
                 }
-                println!();
             }
-			
-			println!("\nPentomino sums:");
-			for pentomino in pentominos.iter() {
-				let mut pentomino_sum = 0;
-				for coord in pentomino.get_cells_with_offset() {
-					for j in 0..hook_number_assignments[i].0.len() {
-						if hook_number_assignments[i].0[j].cells().contains(&coord) {
-							pentomino_sum += hook_number_assignments[i].1[j];
-							break;
-						}
-					}
-				}
-				println!("{}: {}", pentomino.class, pentomino_sum);
-			}
-			
-            println!("\nProduct of empty region areas: {}", grid::pentominos_empty_cell_product(&pentominos));
-            break;
-        }
-	}
-	println!("\nTotal execution time: {:?}", start_instant.elapsed());
+            println!();
+        }
+    }
+    if report.violations.is_empty() {
+        println!("\n{} is a valid solution, product of empty region areas: {}", grid_path, report.answer);
+        return;
+    }
+    let rules = if report.violations.len() == 1 { "rule" } else { "rules" };
+    println!("\n{} breaks {} {}:", grid_path, report.violations.len(), rules);
+    for violation in report.violations.iter() {
+        println!("{}", violation);
+    }
+    std::process::exit(1);
+}
+
+/// Verify the solutions in a JSON solutions file against the puzzle, exiting
+/// with status 1 if any is invalid.
+fn check(puzzle:&Puzzle, path:&str, json:bool) {
+    let input = match std::fs::read_to_string(path) {
+        Ok(input) => input,
+        Err(e) => {
+            println!("Could not read solutions file '{}': {}", path, e);
+            std::process::exit(2);
+        }
+    };
+    match sept2025::json::solutions_from_json(puzzle, &input) {
+        Ok(solutions) => {
+            if json {
+                println!("{}", sept2025::json::solutions_to_json(puzzle, &solutions));
+                return;
+            }
+            for solution in solutions.iter() {
+                solution.print();
+            }
+            println!("\nAll {} solutions in {} are valid", solutions.len(), path);
+        },
+        Err(e) => {
+            println!("Invalid solutions file {}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Decode a SAT solver's model of the puzzle's CNF encoding and print the
+/// solution, exiting with status 1 if it is not a valid solution.
+fn read_model(puzzle:&Puzzle, path:&str, json:bool) {
+    let model = match sept2025::sat::read_model(path) {
+        Ok(model) => model,
+        Err(e) => {
+            println!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    let encoding = HooksCnf::new(puzzle, &grid::pentomino_classes(puzzle));
+    match encoding.decode(puzzle, &model) {
+        Ok(solution) => {
+            if json {
+                println!("{}", sept2025::json::solutions_to_json(puzzle, &[solution]));
+            }
+            else {
+                solution.print();
+            }
+        },
+        Err(e) => {
+            println!("Invalid model {}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,237 @@
+use crate::grid::Coord;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Mutex, OnceLock};
+
+/// Number of cells in each piece unless a puzzle sets another size
+pub const DEFAULT_PIECE_SIZE:usize = 5;
+/// Largest supported piece size. The orientations of the 11-cell polyominos
+/// do not fit in a `ShapeTable`, and larger sizes take too long to enumerate.
+pub const MAX_PIECE_SIZE:usize = 10;
+
+/// The five free tetrominos with a base shape of each
+const TETROMINOS:[(&str, &str); 5] = [
+    ("I", "####"),
+    ("L", "###\n#.."),
+    ("O", "##\n##"),
+    ("S", ".##\n##."),
+    ("T", "###\n.#.")];
+
+/// The twelve free pentominos with a base shape of each
+const PENTOMINOS:[(&str, &str); 12] = [
+    ("F", ".##\n##.\n.#."),
+    ("I", "#####"),
+    ("L", "#.\n#.\n#.\n##"),
+    ("N", "##..\n.###"),
+    ("P", "##\n##\n#."),
+    ("T", "###\n.#.\n.#."),
+    ("U", "#.#\n###"),
+    ("V", "#..\n#..\n###"),
+    ("W", "#..\n##.\n.##"),
+    ("X", ".#.\n###\n.#."),
+    ("Y", "..#.\n####"),
+    ("Z", "..#\n###\n#..")];
+
+/// Free polyominos already enumerated, by size
+static FREE_POLYOMINOS:OnceLock<Mutex<HashMap<usize, &'static [Polyomino]>>> = OnceLock::new();
+
+/// A free polyomino: a connected shape of cells, identified up to rotation
+/// and reflection.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Polyomino {
+    pub name:&'static str,
+    /// Cells of the canonical orientation, the orientation whose cells come
+    /// first in row-major order, with its bounding box at the origin
+    pub cells:Vec<Coord>
+}
+impl Polyomino {
+    /// Return every distinct orientation of the polyomino under rotation and
+    /// reflection, starting with the canonical one.
+    pub fn orientations(&self) -> Vec<Vec<Coord>> {
+        let mut orientations:Vec<Vec<Coord>> = Vec::new();
+        for orientation in transforms(&self.cells) {
+            if !orientations.contains(&orientation) {
+                orientations.push(orientation);
+            }
+        }
+        orientations.sort_by_key(|cells| key(cells));
+        return orientations;
+    }
+    /// Returns true if the polyomino contains a fully occupied 2x2 block.
+    pub fn has_2x2(&self) -> bool {
+        return self.cells.iter().any(|c| [(1, 0), (0, 1), (1, 1)].iter().all(|(dx, dy)| self.cells.contains(&Coord::new(c.x + dx, c.y + dy))));
+    }
+}
+
+/// Parse a shape with `#` for its cells, one row per line.
+pub fn parse_shape(shape:&str) -> Vec<Coord> {
+    let mut cells:Vec<Coord> = Vec::new();
+    for (y, row) in shape.lines().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            if c == '#' {
+                cells.push(Coord::new(x as isize, y as isize));
+            }
+        }
+    }
+    return normalize(&cells);
+}
+
+/// Return the cells in row-major order, as `(y, x)` pairs.
+fn key(cells:&[Coord]) -> Vec<(isize, isize)> {
+    return cells.iter().map(|c| (c.y, c.x)).collect();
+}
+
+/// Move cells so their bounding box is at the origin, in row-major order.
+fn normalize(cells:&[Coord]) -> Vec<Coord> {
+    let min_x = cells.iter().map(|c| c.x).min().unwrap_or(0);
+    let min_y = cells.iter().map(|c| c.y).min().unwrap_or(0);
+    let mut normalized:Vec<Coord> = cells.iter().map(|c| Coord::new(c.x - min_x, c.y - min_y)).collect();
+    normalized.sort_by_key(|c| (c.y, c.x));
+    return normalized;
+}
+
+/// Return the eight rotations and reflections of a shape, normalized.
+fn transforms(cells:&[Coord]) -> Vec<Vec<Coord>> {
+    let mut transforms:Vec<Vec<Coord>> = Vec::new();
+    for reflect in [false, true] {
+        let mut current:Vec<Coord> = cells.iter().map(|c| if reflect { Coord::new(-c.x, c.y) } else { *c }).collect();
+        for _ in 0..4 {
+            current = current.iter().map(|c| Coord::new(-c.y, c.x)).collect();
+            transforms.push(normalize(&current));
+        }
+    }
+    return transforms;
+}
+
+/// Return the canonical orientation of a shape.
+fn canonical(cells:&[Coord]) -> Vec<Coord> {
+    return transforms(cells).into_iter().min_by_key(|cells| key(cells)).unwrap();
+}
+
+/// Return the standard names and shapes of the free polyominos of `n` cells,
+/// if they have standard names.
+fn named_shapes(n:usize) -> Option<&'static [(&'static str, &'static str)]> {
+    return match n {
+        4 => Some(&TETROMINOS),
+        5 => Some(&PENTOMINOS),
+        _ => None
+    };
+}
+
+/// Return the word for a polyomino of `n` cells, such as `pentomino`.
+pub fn piece_noun(n:usize) -> String {
+    return match n {
+        1 => "monomino".to_string(),
+        2 => "domino".to_string(),
+        3 => "tromino".to_string(),
+        4 => "tetromino".to_string(),
+        5 => "pentomino".to_string(),
+        6 => "hexomino".to_string(),
+        7 => "heptomino".to_string(),
+        8 => "octomino".to_string(),
+        9 => "nonomino".to_string(),
+        10 => "decomino".to_string(),
+        n => format!("{}-omino", n)
+    };
+}
+
+/// Parse a piece size given as a number or as the plural of a polyomino word,
+/// such as `hexominos`. Sizes above `MAX_PIECE_SIZE` are rejected.
+pub fn parse_piece_size(token:&str) -> Option<usize> {
+    if let Ok(n) = token.parse::<usize>() {
+        return if n > 0 && n <= MAX_PIECE_SIZE { Some(n) } else { None };
+    }
+    return (1..=MAX_PIECE_SIZE).find(|n| token == format!("{}s", piece_noun(*n)) || token == format!("{}es", piece_noun(*n)));
+}
+
+/// Enumerate the canonical orientations of the free polyominos of `n` cells
+/// by adding a cell to each polyomino of `n - 1` cells in every possible way.
+fn enumerate(n:usize) -> Vec<Vec<Coord>> {
+    let mut shapes:BTreeSet<Vec<(isize, isize)>> = BTreeSet::new();
+    if n > 0 {
+        shapes.insert(vec![(0, 0)]);
+    }
+    for _ in 1..n {
+        let mut next:BTreeSet<Vec<(isize, isize)>> = BTreeSet::new();
+        for shape in shapes.iter() {
+            let cells:Vec<Coord> = shape.iter().map(|(y, x)| Coord::new(*x, *y)).collect();
+            for cell in cells.iter() {
+                for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                    let added = Coord::new(cell.x + dx, cell.y + dy);
+                    if cells.contains(&added) {
+                        continue;
+                    }
+                    let mut grown = cells.clone();
+                    grown.push(added);
+                    next.insert(key(&canonical(&grown)));
+                }
+            }
+        }
+        shapes = next;
+    }
+    return shapes.into_iter().map(|shape| shape.into_iter().map(|(y, x)| Coord::new(x, y)).collect()).collect();
+}
+
+/// Return the free polyominos of `n` cells. Tetrominos and pentominos have
+/// their standard letter names and are listed alphabetically. Hexominos have
+/// no standard letters, so this crate names them `H01` to `H35` in order of
+/// their canonical orientations, with the straight hexomino first, rather
+/// than after any published catalogue. The README lists them; larger sizes
+/// are numbered the same way with four digits and the size as a prefix, as
+/// in `7-0001`, so names sort in numeric order.
+pub fn free_polyominos(n:usize) -> &'static [Polyomino] {
+    let mut cache = FREE_POLYOMINOS.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap();
+    if let Some(polyominos) = cache.get(&n) {
+        return polyominos;
+    }
+    let shapes = enumerate(n);
+    let mut polyominos:Vec<Polyomino> = match named_shapes(n) {
+        Some(named) => named.iter().map(|(name, shape)| Polyomino{name:name, cells:canonical(&parse_shape(shape))}).collect(),
+        None => shapes.iter().enumerate().map(|(i, cells)| {
+            let name = match n {
+                6 => format!("H{:02}", i + 1),
+                _ => format!("{}-{:04}", n, i + 1)
+            };
+            Polyomino{name:Box::leak(name.into_boxed_str()), cells:cells.clone()}
+        }).collect()
+    };
+    debug_assert!(polyominos.len() == shapes.len() && polyominos.iter().all(|p| shapes.contains(&p.cells)));
+    polyominos.sort_by_key(|p| p.name);
+    let polyominos:&'static [Polyomino] = Box::leak(polyominos.into_boxed_slice());
+    cache.insert(n, polyominos);
+    return polyominos;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{free_polyominos, parse_piece_size, MAX_PIECE_SIZE};
+
+    #[test]
+    fn enumerates_the_free_pentominos_and_hexominos() {
+        let pentominos = free_polyominos(5);
+        assert_eq!(pentominos.iter().map(|p| p.name).collect::<Vec<&str>>(), ["F", "I", "L", "N", "P", "T", "U", "V", "W", "X", "Y", "Z"]);
+        let without_2x2:Vec<&str> = pentominos.iter().filter(|p| !p.has_2x2()).map(|p| p.name).collect();
+        assert_eq!(without_2x2.len(), 11);
+        assert!(!without_2x2.contains(&"P"));
+        assert_eq!(free_polyominos(6).len(), 35);
+        assert_eq!(free_polyominos(6).iter().filter(|p| p.has_2x2()).count(), 8);
+    }
+
+    #[test]
+    fn numbered_names_sort_in_numeric_order() {
+        let heptominos = free_polyominos(7);
+        assert_eq!(heptominos.len(), 108);
+        let names:Vec<String> = (1..=108).map(|i| format!("7-{:04}", i)).collect();
+        assert_eq!(heptominos.iter().map(|p| p.name).collect::<Vec<&str>>(), names);
+    }
+
+    #[test]
+    fn piece_sizes_are_bounded() {
+        assert_eq!(parse_piece_size("hexominos"), Some(6));
+        assert_eq!(parse_piece_size("octominoes"), Some(8));
+        assert_eq!(parse_piece_size("decominos"), Some(10));
+        assert_eq!(parse_piece_size(&MAX_PIECE_SIZE.to_string()), Some(MAX_PIECE_SIZE));
+        assert_eq!(parse_piece_size("0"), None);
+        assert_eq!(parse_piece_size(&(MAX_PIECE_SIZE + 1).to_string()), None);
+        assert_eq!(parse_piece_size("15"), None);
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Work queue shared by a pool of worker threads.
+///
+/// Workers take tasks from the front of the queue until no tasks remain and
+/// no other worker is still running a task that might add more. A worker
+/// running a large task can check `hungry()` and split off part of its work
+/// with `push()` so that idle workers can take it.
+pub struct WorkQueue<T> {
+    state:Mutex<QueueState<T>>,
+    changed:Condvar,
+    idle:AtomicUsize,
+    queued:AtomicUsize,
+    created:AtomicUsize,
+    completed:AtomicUsize
+}
+struct QueueState<T> {
+    tasks:VecDeque<T>,
+    active:usize
+}
+impl<T> WorkQueue<T> {
+    pub fn new(tasks:Vec<T>) -> WorkQueue<T> {
+        let tasks_len = tasks.len();
+        let queued = AtomicUsize::new(tasks_len);
+        return WorkQueue{state:Mutex::new(QueueState{tasks:VecDeque::from(tasks), active:0}), changed:Condvar::new(), idle:AtomicUsize::new(0), queued:queued, created:AtomicUsize::new(tasks_len), completed:AtomicUsize::new(0)};
+    }
+    /// Take the next task, waiting while the queue is empty and other
+    /// workers are still running. Returns `None` once all work is done. Each
+    /// task taken must be followed by a call to `finish()`.
+    pub fn take(&self) -> Option<T> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(task) = state.tasks.pop_front() {
+                self.queued.store(state.tasks.len(), Ordering::Relaxed);
+                state.active += 1;
+                return Some(task);
+            }
+            if state.active == 0 {
+                self.changed.notify_all();
+                return None;
+            }
+            self.idle.fetch_add(1, Ordering::Relaxed);
+            state = self.changed.wait(state).unwrap();
+            self.idle.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+    /// Mark a task returned by `take()` as complete.
+    pub fn finish(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.active -= 1;
+        self.completed.fetch_add(1, Ordering::Relaxed);
+        if state.active == 0 {
+            self.changed.notify_all();
+        }
+    }
+    /// Add tasks to the back of the queue.
+    pub fn push(&self, tasks:impl IntoIterator<Item = T>) {
+        let mut state = self.state.lock().unwrap();
+        let before = state.tasks.len();
+        state.tasks.extend(tasks);
+        self.created.fetch_add(state.tasks.len() - before, Ordering::Relaxed);
+        self.queued.store(state.tasks.len(), Ordering::Relaxed);
+        self.changed.notify_all();
+    }
+    /// Add tasks to the front of the queue, keeping their order, so that
+    /// they are taken before the tasks already queued.
+    pub fn push_front(&self, tasks:Vec<T>) {
+        let mut state = self.state.lock().unwrap();
+        self.created.fetch_add(tasks.len(), Ordering::Relaxed);
+        for task in tasks.into_iter().rev() {
+            state.tasks.push_front(task);
+        }
+        self.queued.store(state.tasks.len(), Ordering::Relaxed);
+        self.changed.notify_all();
+    }
+    /// Drop the tasks still queued, so that workers stop once the running
+    /// tasks finish.
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.tasks.clear();
+        self.queued.store(0, Ordering::Relaxed);
+    }
+    /// Returns true if any worker is waiting for a task and the queue is
+    /// empty.
+    pub fn hungry(&self) -> bool {
+        return self.idle.load(Ordering::Relaxed) > 0 && self.queued.load(Ordering::Relaxed) == 0;
+    }
+    /// Return the number of tasks completed and the number of tasks created.
+    pub fn progress(&self) -> (usize, usize) {
+        return (self.completed.load(Ordering::Relaxed), self.created.load(Ordering::Relaxed));
+    }
+}
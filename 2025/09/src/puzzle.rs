@@ -0,0 +1,340 @@
+use crate::bitboard::MAX_DIM;
+use crate::constraint::{hooks_constraints, Constraint};
+use crate::grid::Coord;
+use crate::polyomino::{parse_piece_size, piece_noun, DEFAULT_PIECE_SIZE, MAX_PIECE_SIZE};
+use crate::shape::ShapeTable;
+use std::sync::Arc;
+
+/// The September 2025 puzzle (Hooks 11).
+///
+/// The inner 9x9 block holds the given digits. The border rows and columns
+/// hold the edge clues for each row and column, read from that edge: a digit
+/// is the first digit seen from that edge, and a letter is the first
+/// pentomino seen from that edge.
+pub const SEPTEMBER_PUZZLE:&str = r#"
+# Hooks 11 - Jane Street September 2025
+. . . . . . . 7 . . .
+I . . . . 5 . . . . U
+. . . . 4 . . . . . .
+. . . . . . . . . . .
+6 . . . . . . . . . X
+. . . . . 1 . . . . .
+N . . . . . . . . . 2
+. . . . . . . . . . .
+. . . . . . 8 . . . .
+Z . . . . 9 . . . . V
+. . . 3 . . . . . . ."#;
+
+/// The edge of the grid from which a row or column clue is read.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+    Top,
+    Bottom
+}
+impl Side {
+    /// Return the cells of the given row or column of a `dim` x `dim` grid,
+    /// in the order they are encountered when traversing from this edge.
+    pub fn line_cells(&self, line:usize, dim:usize) -> Vec<Coord> {
+        let line = line as isize;
+        let dim = dim as isize;
+        match self {
+            Side::Left => (0..dim).map(|x| Coord::new(x, line)).collect(),
+            Side::Right => (0..dim).rev().map(|x| Coord::new(x, line)).collect(),
+            Side::Top => (0..dim).map(|y| Coord::new(line, y)).collect(),
+            Side::Bottom => (0..dim).rev().map(|y| Coord::new(line, y)).collect(),
+        }
+    }
+    /// Returns true if the clue applies to a row rather than a column.
+    pub fn is_row(&self) -> bool {
+        return matches!(self, Side::Left | Side::Right);
+    }
+}
+impl std::fmt::Display for Side {
+    fn fmt(&self, f: &mut  std::fmt::Formatter) ->  std::fmt::Result {
+        match self {
+            Side::Left => write!(f, "left"),
+            Side::Right => write!(f, "right"),
+            Side::Top => write!(f, "top"),
+            Side::Bottom => write!(f, "bottom"),
+        }
+    }
+}
+
+/// What an edge clue requires of its row or column: the first filled cell
+/// encountered from the edge, or the digits in all the filled cells of the
+/// line.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ClueValue {
+    /// The first filled cell holds the digit
+    Digit(usize),
+    /// The first filled cell belongs to a piece of the class
+    Pentomino(String),
+    /// The filled cells sum to the value
+    Sum(usize),
+    /// The product of the filled cells is the value
+    Product(usize),
+    /// The greatest common divisor of the filled cells is the value
+    Gcd(usize)
+}
+impl ClueValue {
+    /// Returns true if the clue is on the first filled cell seen from its
+    /// edge rather than on every filled cell of its line.
+    pub fn is_first_seen(&self) -> bool {
+        return matches!(self, ClueValue::Digit(_) | ClueValue::Pentomino(_));
+    }
+}
+impl std::fmt::Display for ClueValue {
+    /// Write the clue as its token in a puzzle description.
+    fn fmt(&self, f: &mut  std::fmt::Formatter) ->  std::fmt::Result {
+        match self {
+            ClueValue::Digit(d) => write!(f, "{}", d),
+            ClueValue::Pentomino(class) => write!(f, "{}", class),
+            ClueValue::Sum(n) => write!(f, "sum={}", n),
+            ClueValue::Product(n) => write!(f, "prod={}", n),
+            ClueValue::Gcd(n) => write!(f, "gcd={}", n)
+        }
+    }
+}
+
+/// A clue given on the edge of a row or column.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EdgeClue {
+    pub side:Side,
+    pub line:usize,
+    pub value:ClueValue
+}
+impl EdgeClue {
+    /// Return the cells of the clued line in the order they are traversed
+    /// from the clue's edge.
+    pub fn cells(&self, dim:usize) -> Vec<Coord> {
+        return self.side.line_cells(self.line, dim);
+    }
+}
+impl std::fmt::Display for EdgeClue {
+    fn fmt(&self, f: &mut  std::fmt::Formatter) ->  std::fmt::Result {
+        let kind = if self.side.is_row() { "row" } else { "column" };
+        return write!(f, "{} {} from the {}: {}", kind, self.line, self.side, self.value);
+    }
+}
+
+/// A Hooks puzzle instance: the given digits on the grid and the clues on the
+/// grid edges. A `dim` x `dim` puzzle is partitioned into hooks of size 1 to
+/// `dim` and holds one 1, two 2s, etc. up to `dim` copies of `dim`, and its
+/// filled cells are divided into distinct pieces of `piece_size` cells.
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    pub dim:usize,
+    /// Number of cells in each piece: 5 for pentominos
+    pub piece_size:usize,
+    /// Orientations and placements of the pieces on the grid
+    pub shapes:&'static ShapeTable,
+    pub givens:Vec<(Coord, usize)>,
+    pub clues:Vec<EdgeClue>,
+    /// Rules pentomino assignments are checked against, in the order they are
+    /// checked
+    pub constraints:Vec<Arc<dyn Constraint>>
+}
+impl std::fmt::Display for Puzzle {
+    /// Write the puzzle in the description format read by `Puzzle::parse`.
+    fn fmt(&self, f: &mut  std::fmt::Formatter) ->  std::fmt::Result {
+        if self.piece_size != DEFAULT_PIECE_SIZE {
+            writeln!(f, "pieces {}", self.piece_size)?;
+        }
+        let size = self.dim + 2;
+        let mut rows = vec![vec![".".to_string(); size]; size];
+        for line in 0..self.dim {
+            rows[0][line + 1] = self.clue_token(Side::Top, line);
+            rows[size - 1][line + 1] = self.clue_token(Side::Bottom, line);
+            rows[line + 1][0] = self.clue_token(Side::Left, line);
+            rows[line + 1][size - 1] = self.clue_token(Side::Right, line);
+        }
+        for (coord, d) in self.givens.iter() {
+            rows[coord.y as usize + 1][coord.x as usize + 1] = d.to_string();
+        }
+        for row in rows.iter() {
+            writeln!(f, "{}", row.join(" "))?;
+        }
+        return Ok(());
+    }
+}
+
+impl Puzzle {
+    /// Parse a puzzle description.
+    ///
+    /// A description is a square block of whitespace-separated tokens, with
+    /// `#` starting a comment. The inner block gives the grid, where `.` is an
+    /// unspecified cell and a number is a given digit. The first and last
+    /// rows and columns hold the clues read from the top, bottom, left and
+    /// right edges respectively, where `.` is no clue, a number is a digit
+    /// clue and a piece name such as `F`, `H07` or `7-0012` is a piece clue.
+    /// `sum=N`, `prod=N` and `gcd=N` are clues on the sum, product and
+    /// greatest common divisor of the filled cells in the line. The four
+    /// corners must be `.`.
+    ///
+    /// The pieces are pentominos unless the block is preceded by a line
+    /// `pieces N`, where N is the number of cells in each piece, at most
+    /// `MAX_PIECE_SIZE`, or a word such as `tetrominos` or `hexominos`.
+    pub fn parse(input:&str) -> Result<Puzzle, String> {
+        let mut rows:Vec<Vec<&str>> = Vec::new();
+        let mut piece_size = DEFAULT_PIECE_SIZE;
+        for line in input.lines() {
+            let content = match line.find('#') {
+                Some(i) => &line[..i],
+                None => line
+            };
+            let tokens:Vec<&str> = content.split_whitespace().collect();
+            if tokens.first() == Some(&"pieces") {
+                if !rows.is_empty() {
+                    return Err("The pieces directive must come before the puzzle grid".to_string());
+                }
+                piece_size = match tokens[1..] {
+                    [token] => match parse_piece_size(token) {
+                        Some(n) => n,
+                        None => return Err(format!("Could not parse piece size '{}': expected a number of cells from 1 to {} or a word such as 'hexominos'", token, MAX_PIECE_SIZE))
+                    },
+                    _ => return Err(format!("Expected 'pieces N', found '{}'", content.trim()))
+                };
+            }
+            else if !tokens.is_empty() {
+                rows.push(tokens);
+            }
+        }
+        if rows.len() < 3 {
+            return Err(format!("Puzzle must have at least 3 rows including the clue border ({} found)", rows.len()));
+        }
+        let size = rows.len();
+        let dim = size - 2;
+        if dim > MAX_DIM {
+            return Err(format!("Puzzle grids larger than {}x{} are not supported ({}x{} provided)", MAX_DIM, MAX_DIM, dim, dim));
+        }
+        if piece_size > dim * (dim + 1) / 2 {
+            return Err(format!("A {} has {} cells, more than the {} filled cells of a {}x{} puzzle", piece_noun(piece_size), piece_size, dim * (dim + 1) / 2, dim, dim));
+        }
+        if !(dim * (dim + 1) / 2).is_multiple_of(piece_size) {
+            return Err(format!("The {} filled cells of a {}x{} puzzle cannot be divided into {}s", dim * (dim + 1) / 2, dim, dim, piece_noun(piece_size)));
+        }
+        for (y, row) in rows.iter().enumerate() {
+            if row.len() != size {
+                return Err(format!("Puzzle row {} has {} entries, expected {}", y, row.len(), size));
+            }
+        }
+        for (x, y) in [(0, 0), (size - 1, 0), (0, size - 1), (size - 1, size - 1)] {
+            if rows[y][x] != "." {
+                return Err(format!("Puzzle corner ({},{}) must be '.' (found '{}')", x, y, rows[y][x]));
+            }
+        }
+        let mut givens:Vec<(Coord, usize)> = Vec::new();
+        for y in 1..=dim {
+            for x in 1..=dim {
+                let token = rows[y][x];
+                if token == "." {
+                    continue;
+                }
+                let coord = Coord::new((x - 1) as isize, (y - 1) as isize);
+                match token.parse::<usize>() {
+                    Ok(d) if (1..=dim).contains(&d) => givens.push((coord, d)),
+                    _ => return Err(format!("Invalid given digit '{}' at {}", token, coord))
+                }
+            }
+        }
+        let mut clues:Vec<EdgeClue> = Vec::new();
+        for line in 0..dim {
+            for (side, token) in [(Side::Top, rows[0][line + 1]), (Side::Bottom, rows[size - 1][line + 1])] {
+                if let Some(value) = Puzzle::parse_clue_value(token, dim)? {
+                    clues.push(EdgeClue{side:side, line:line, value:value});
+                }
+            }
+        }
+        for line in 0..dim {
+            for (side, token) in [(Side::Left, rows[line + 1][0]), (Side::Right, rows[line + 1][size - 1])] {
+                if let Some(value) = Puzzle::parse_clue_value(token, dim)? {
+                    clues.push(EdgeClue{side:side, line:line, value:value});
+                }
+            }
+        }
+        return Ok(Puzzle::with_piece_size(dim, piece_size, givens, clues));
+    }
+    /// Build a pentomino puzzle with the rules of Hooks 11 and the given
+    /// digits and edge clues.
+    pub fn new(dim:usize, givens:Vec<(Coord, usize)>, clues:Vec<EdgeClue>) -> Puzzle {
+        return Puzzle::with_piece_size(dim, DEFAULT_PIECE_SIZE, givens, clues);
+    }
+    /// Build a puzzle with the rules of Hooks 11 and pieces of `piece_size`
+    /// cells.
+    pub fn with_piece_size(dim:usize, piece_size:usize, givens:Vec<(Coord, usize)>, clues:Vec<EdgeClue>) -> Puzzle {
+        let constraints = hooks_constraints(piece_size, &givens, &clues);
+        return Puzzle{dim:dim, piece_size:piece_size, shapes:ShapeTable::get(dim, piece_size), givens:givens, clues:clues, constraints:constraints};
+    }
+    fn parse_clue_value(token:&str, dim:usize) -> Result<Option<ClueValue>, String> {
+        if token == "." {
+            return Ok(None);
+        }
+        if let Ok(d) = token.parse::<usize>() {
+            if !(1..=dim).contains(&d) {
+                return Err(format!("Digit clue {} is outside the range 1-{}", d, dim));
+            }
+            return Ok(Some(ClueValue::Digit(d)));
+        }
+        if let Some((kind, value)) = token.split_once('=') {
+            let n = match value.parse::<usize>() {
+                Ok(n) if n >= 1 => n,
+                _ => return Err(format!("Could not parse the value of clue '{}' as a positive integer", token))
+            };
+            match kind {
+                "sum" => return Ok(Some(ClueValue::Sum(n))),
+                "prod" => return Ok(Some(ClueValue::Product(n))),
+                "gcd" if n <= dim => return Ok(Some(ClueValue::Gcd(n))),
+                "gcd" => return Err(format!("GCD clue {} is outside the range 1-{}", n, dim)),
+                _ => return Err(format!("Unknown clue type '{}' in clue '{}', expected sum, prod or gcd", kind, token))
+            }
+        }
+        if token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Ok(Some(ClueValue::Pentomino(token.to_string())));
+        }
+        return Err(format!("Could not parse clue '{}'", token));
+    }
+    /// Read and parse a puzzle description file.
+    pub fn from_file(path:&str) -> Result<Puzzle, String> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Puzzle::parse(&contents),
+            Err(e) => Err(format!("Could not read puzzle file '{}': {}", path, e))
+        }
+    }
+    /// Return the number of filled cells in a solution.
+    pub fn occupied_cells(&self) -> usize {
+        return self.dim * (self.dim + 1) / 2;
+    }
+    /// Return the number of pieces the filled cells are divided into.
+    pub fn piece_count(&self) -> usize {
+        return self.occupied_cells() / self.piece_size;
+    }
+    /// Return the token for a clue, or `.` if there is none.
+    fn clue_token(&self, side:Side, line:usize) -> String {
+        match self.clues.iter().find(|c| c.side == side && c.line == line) {
+            Some(clue) => clue.value.to_string(),
+            None => ".".to_string()
+        }
+    }
+    /// Return the edge clues that specify a digit, with the clued digit.
+    pub fn digit_clues(&self) -> Vec<(&EdgeClue, usize)> {
+        let mut digit_clues = Vec::new();
+        for clue in self.clues.iter() {
+            if let ClueValue::Digit(d) = clue.value {
+                digit_clues.push((clue, d));
+            }
+        }
+        return digit_clues;
+    }
+    /// Return the edge clues that specify a pentomino, with the clued class.
+    pub fn pentomino_clues(&self) -> Vec<(&EdgeClue, &str)> {
+        let mut pentomino_clues = Vec::new();
+        for clue in self.clues.iter() {
+            if let ClueValue::Pentomino(class) = &clue.value {
+                pentomino_clues.push((clue, class.as_str()));
+            }
+        }
+        return pentomino_clues;
+    }
+}
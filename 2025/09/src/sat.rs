@@ -0,0 +1,609 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
+use crate::bitboard::Bitboard;
+use crate::constraint::{gcd, SUM_MODULUS};
+use crate::grid::{self, Coord, Hook, HookOrientation};
+use crate::puzzle::{ClueValue, Puzzle};
+use crate::shape::{Placement, ShapeId};
+use crate::{verify_solution, HooksSolution};
+
+/// Boolean formula in conjunctive normal form, with DIMACS literals.
+pub struct Cnf {
+    pub num_vars:usize,
+    pub clauses:Vec<Vec<i32>>,
+    /// Names of the variables that describe the solution
+    pub names:Vec<(i32, String)>,
+    /// Variable fixed to true, used for constant literals
+    truth:i32
+}
+impl Cnf {
+    pub fn new() -> Cnf {
+        let mut cnf = Cnf{num_vars:0, clauses:Vec::new(), names:Vec::new(), truth:0};
+        cnf.truth = cnf.var();
+        cnf.clause(&[cnf.truth]);
+        return cnf;
+    }
+    pub fn var(&mut self) -> i32 {
+        self.num_vars += 1;
+        return self.num_vars as i32;
+    }
+    pub fn named(&mut self, name:String) -> i32 {
+        let var = self.var();
+        self.names.push((var, name));
+        return var;
+    }
+    pub fn clause(&mut self, lits:&[i32]) {
+        self.clauses.push(lits.to_vec());
+    }
+    pub fn truth(&self) -> i32 {
+        return self.truth;
+    }
+    /// Return a variable equivalent to the conjunction of `lits`.
+    pub fn and(&mut self, lits:&[i32]) -> i32 {
+        let var = self.var();
+        let mut clause = vec![var];
+        for lit in lits {
+            self.clause(&[-var, *lit]);
+            clause.push(-lit);
+        }
+        self.clause(&clause);
+        return var;
+    }
+    /// Return a variable equivalent to the disjunction of `lits`.
+    pub fn or(&mut self, lits:&[i32]) -> i32 {
+        let var = self.var();
+        let mut clause = vec![-var];
+        for lit in lits {
+            self.clause(&[var, -lit]);
+            clause.push(*lit);
+        }
+        self.clause(&clause);
+        return var;
+    }
+    /// Require exactly one of `lits` to be true, with pairwise clauses.
+    pub fn exactly_one(&mut self, lits:&[i32]) {
+        self.clause(lits);
+        for i in 0..lits.len() {
+            for j in i + 1..lits.len() {
+                self.clause(&[-lits[i], -lits[j]]);
+            }
+        }
+    }
+    /// Require between `min` and `max` of `lits` to be true, with a
+    /// sequential counter. Each counter variable is defined by equivalence,
+    /// so it adds no freedom to the models.
+    pub fn count_between(&mut self, lits:&[i32], min:usize, max:usize) {
+        // at_least[j - 1] holds "at least j of the literals so far are true"
+        let mut at_least:Vec<i32> = vec![-self.truth; max + 1];
+        for (i, lit) in lits.iter().enumerate() {
+            let mut next = at_least.clone();
+            for j in 1..=(max + 1).min(i + 1) {
+                let carried = if j == 1 { *lit } else { self.and(&[at_least[j - 2], *lit]) };
+                next[j - 1] = self.or(&[at_least[j - 1], carried]);
+            }
+            at_least = next;
+        }
+        if min > 0 {
+            self.clause(&[at_least[min - 1]]);
+        }
+        self.clause(&[-at_least[max]]);
+    }
+}
+impl Default for Cnf {
+    fn default() -> Cnf {
+        return Cnf::new();
+    }
+}
+
+/// CNF encoding of a Hooks puzzle, with the variables needed to decode a
+/// model into a solution.
+///
+/// The hooks are encoded by the position of the square left for the smaller
+/// hooks after placing each hook, with one orientation variable per hook
+/// size. Hook numbers are a permutation, a cell's digit is its hook's number
+/// when the cell is filled, and sequential counters fix the count of each
+/// digit. Pentominos are encoded by one variable per placement, each class is
+/// placed at most once, and each filled cell is covered by exactly one
+/// placement. Pentomino sums are constrained through the digit residues
+/// modulo 5 of pairs of cells. Sum, product and GCD clues are encoded by the
+/// values their line can reach after each of its cells. Connectivity is
+/// encoded by reachability from the first filled cell in row-major order in
+/// at most as many steps as there are filled cells. Every auxiliary variable
+/// is defined by equivalence, so each solution of the puzzle has exactly one
+/// model.
+pub struct HooksCnf {
+    pub cnf:Cnf,
+    /// Square variables by hook size, with the top-left cell of the square
+    squares:Vec<Vec<(Coord, i32)>>,
+    /// Orientation variables by hook size
+    orientations:Vec<Vec<(HookOrientation, i32)>>,
+    /// Assignment variables by hook size, indexed by number
+    assignments:Vec<Vec<i32>>,
+    placements:Vec<(Placement, i32)>
+}
+
+/// Return the corner of a hook of size `s` placed in the square at `square`
+/// with the given orientation, and the top-left cell of the square left for
+/// the smaller hooks, as in `hook_recurse`.
+fn hook_in_square(s:usize, square:&Coord, orientation:HookOrientation) -> (Coord, Coord) {
+    let far = s as isize - 1;
+    return match orientation {
+        HookOrientation::NE => (Coord::new(square.x + far, square.y), Coord::new(square.x, square.y + 1)),
+        HookOrientation::SE => (Coord::new(square.x + far, square.y + far), Coord::new(square.x, square.y)),
+        HookOrientation::SW => (Coord::new(square.x, square.y + far), Coord::new(square.x + 1, square.y)),
+        HookOrientation::NW => (Coord::new(square.x, square.y), Coord::new(square.x + 1, square.y + 1))
+    };
+}
+
+/// Require the digits in the filled cells of a line to combine to `target`,
+/// folding them in order with `step` starting from `start`. `filled[i]` is
+/// true if the i-th cell of the line is filled and `digits[i][d]` if it is
+/// filled and holds d. `step` returns `None` for values that can no longer
+/// reach `target`. One variable is defined by equivalence for each value
+/// reachable after each cell.
+fn fold_line(cnf:&mut Cnf, filled:&[i32], digits:&[Vec<i32>], start:usize, target:usize, step:impl Fn(usize, usize) -> Option<usize>) {
+    let mut values:BTreeMap<usize, i32> = BTreeMap::from([(start, cnf.truth())]);
+    for i in 0..filled.len() {
+        let mut terms:BTreeMap<usize, Vec<i32>> = BTreeMap::new();
+        for (value, var) in values.iter() {
+            terms.entry(*value).or_default().push(cnf.and(&[*var, -filled[i]]));
+            for d in 1..digits[i].len() {
+                if let Some(next) = step(*value, d) {
+                    terms.entry(next).or_default().push(cnf.and(&[*var, digits[i][d]]));
+                }
+            }
+        }
+        values = terms.into_iter().map(|(value, terms)| (value, cnf.or(&terms))).collect();
+    }
+    let reached = values.get(&target).copied().unwrap_or(-cnf.truth());
+    cnf.clause(&[reached]);
+}
+
+impl HooksCnf {
+    pub fn new(puzzle:&Puzzle, classes:&[ShapeId]) -> HooksCnf {
+        let dim = puzzle.dim;
+        let shapes = puzzle.shapes;
+        let cells = dim * dim;
+        let mut cnf = Cnf::new();
+        let index = |coord:&Coord| coord.y as usize * dim + coord.x as usize;
+
+        // Hook squares and orientations. membership[c][s] collects the
+        // variables of the hook placements of size s covering cell c.
+        let mut squares:Vec<Vec<(Coord, i32)>> = vec![Vec::new(); dim + 1];
+        let mut orientations:Vec<Vec<(HookOrientation, i32)>> = vec![Vec::new(); dim + 1];
+        for s in 1..=dim {
+            for y in 0..=dim - s {
+                for x in 0..=dim - s {
+                    let var = cnf.named(format!("square {} {} {}", s, x, y));
+                    squares[s].push((Coord::new(x as isize, y as isize), var));
+                }
+            }
+            let vars:Vec<i32> = squares[s].iter().map(|(_, v)| *v).collect();
+            cnf.exactly_one(&vars);
+            if s > 1 {
+                for orientation in [HookOrientation::NW, HookOrientation::NE, HookOrientation::SW, HookOrientation::SE] {
+                    let var = cnf.named(format!("orientation {} {:?}", s, orientation));
+                    orientations[s].push((orientation, var));
+                }
+                let vars:Vec<i32> = orientations[s].iter().map(|(_, v)| *v).collect();
+                cnf.exactly_one(&vars);
+            }
+        }
+        cnf.clause(&[squares[dim][0].1]);
+        let mut membership:Vec<Vec<Vec<i32>>> = vec![vec![Vec::new(); dim + 1]; cells];
+        for s in 1..=dim {
+            for (square, square_var) in squares[s].clone() {
+                let options:Vec<(HookOrientation, i32)> = if s == 1 { vec![(HookOrientation::NE, square_var)] } else {
+                    orientations[s].iter().map(|(o, v)| (*o, cnf.and(&[square_var, *v]))).collect()
+                };
+                for (orientation, var) in options {
+                    let (corner, next) = hook_in_square(s, &square, orientation);
+                    for coord in Hook::new(s, corner, orientation).cells() {
+                        membership[index(&coord)][s].push(var);
+                    }
+                    if s > 1 {
+                        let next_var = squares[s - 1].iter().find(|(c, _)| *c == next).unwrap().1;
+                        cnf.clause(&[-var, next_var]);
+                    }
+                }
+            }
+        }
+
+        // Numbers assigned to hooks, as a permutation
+        let mut assignments:Vec<Vec<i32>> = vec![Vec::new(); dim + 1];
+        for s in 1..=dim {
+            assignments[s].push(0);
+            for d in 1..=dim {
+                let var = cnf.named(format!("assign {} {}", s, d));
+                assignments[s].push(var);
+            }
+            let vars = assignments[s][1..].to_vec();
+            cnf.exactly_one(&vars);
+        }
+        for d in 1..=dim {
+            let vars:Vec<i32> = (1..=dim).map(|s| assignments[s][d]).collect();
+            cnf.exactly_one(&vars);
+        }
+
+        // The number of each cell's hook, its residue modulo 5, and whether
+        // the cell is filled
+        let mut hook_number:Vec<Vec<i32>> = Vec::new();
+        let mut residue:Vec<Vec<i32>> = Vec::new();
+        let mut filled:Vec<i32> = Vec::new();
+        for c in 0..cells {
+            let in_hook:Vec<i32> = (0..=dim).map(|s| if s == 0 || membership[c][s].is_empty() { -cnf.truth() } else { cnf.or(&membership[c][s].clone()) }).collect();
+            let mut numbers = vec![0];
+            for d in 1..=dim {
+                let terms:Vec<i32> = (1..=dim).map(|s| cnf.and(&[in_hook[s], assignments[s][d]])).collect();
+                numbers.push(cnf.or(&terms));
+            }
+            let residues:Vec<i32> = (0..SUM_MODULUS).map(|m| {
+                let terms:Vec<i32> = (1..=dim).filter(|d| d % SUM_MODULUS == m).map(|d| numbers[d]).collect();
+                cnf.or(&terms)
+            }).collect();
+            hook_number.push(numbers);
+            residue.push(residues);
+            filled.push(cnf.named(format!("filled {} {}", c % dim, c / dim)));
+        }
+
+        // Digit counts. cell_digit[c][d] holds "cell c is filled with d".
+        let mut cell_digit:Vec<Vec<i32>> = vec![vec![0; dim + 1]; cells];
+        for d in 1..=dim {
+            let mut digits:Vec<i32> = Vec::new();
+            for c in 0..cells {
+                let var = cnf.and(&[filled[c], hook_number[c][d]]);
+                cnf.names.push((var, format!("digit {} {} {}", c % dim, c / dim, d)));
+                digits.push(var);
+                cell_digit[c][d] = var;
+            }
+            cnf.count_between(&digits, d, d);
+        }
+        // Given digits
+        for (coord, d) in puzzle.givens.iter() {
+            cnf.clause(&[filled[index(coord)]]);
+            cnf.clause(&[hook_number[index(coord)][*d]]);
+        }
+        // 2x2 rule
+        for y in 0..dim - 1 {
+            for x in 0..dim - 1 {
+                let c = y * dim + x;
+                cnf.clause(&[-filled[c], -filled[c + 1], -filled[c + dim], -filled[c + dim + 1]]);
+            }
+        }
+
+        // Pentomino placements, in a fixed order so that the encoding of a
+        // puzzle is always the same
+        let mut placements:Vec<(Placement, i32)> = Vec::new();
+        let mut covering:Vec<Vec<i32>> = vec![Vec::new(); cells];
+        let mut used:Vec<i32> = Vec::new();
+        let mut pair_residues:HashMap<(usize, usize), Vec<i32>> = HashMap::new();
+        for class in classes.iter() {
+            let mut class_vars:Vec<i32> = Vec::new();
+            for pentomino in shapes.placements(*class) {
+                let var = cnf.named(format!("place {} {} {} {}", shapes.name(*class), pentomino.x, pentomino.y, shapes.orientation(&pentomino).rows.join("/")));
+                let cell_indices:Vec<usize> = shapes.mask(&pentomino).coords(dim).iter().map(index).collect();
+                for c in cell_indices.iter() {
+                    covering[*c].push(var);
+                    cnf.clause(&[-var, filled[*c]]);
+                }
+                // Forbid every combination of residues of the pairs
+                // of cells, and the last cell of an odd sized piece,
+                // whose total is not a multiple of 5
+                let mut pair = |a:usize, b:usize, cnf:&mut Cnf| -> Vec<i32> {
+                    return pair_residues.entry((a, b)).or_insert_with(|| (0..SUM_MODULUS).map(|m| {
+                        let terms:Vec<i32> = (0..SUM_MODULUS).map(|m1| cnf.and(&[residue[a][m1], residue[b][(m + SUM_MODULUS - m1) % SUM_MODULUS]])).collect();
+                        cnf.or(&terms)
+                    }).collect()).clone();
+                };
+                let groups:Vec<Vec<i32>> = cell_indices.chunks(2).map(|chunk| match chunk {
+                    [a, b] => pair(*a, *b, &mut cnf),
+                    _ => residue[chunk[0]].clone()
+                }).collect();
+                let mut residues = vec![0; groups.len()];
+                loop {
+                    if residues.iter().sum::<usize>() % SUM_MODULUS != 0 {
+                        let mut clause = vec![-var];
+                        clause.extend(groups.iter().zip(residues.iter()).map(|(group, m)| -group[*m]));
+                        cnf.clause(&clause);
+                    }
+                    // Next combination of residues, as digits of a
+                    // base 5 counter
+                    match residues.iter().position(|m| m + 1 < SUM_MODULUS) {
+                        Some(i) => {
+                            residues[i] += 1;
+                            residues[..i].fill(0);
+                        },
+                        None => break
+                    }
+                }
+                class_vars.push(var);
+                placements.push((pentomino, var));
+            }
+            cnf.count_between(&class_vars, 0, 1);
+            used.push(cnf.or(&class_vars));
+        }
+        cnf.count_between(&used, puzzle.piece_count(), puzzle.piece_count());
+        // Every filled cell is covered by exactly one placement
+        for c in 0..cells {
+            let mut clause = vec![-filled[c]];
+            clause.extend(covering[c].iter());
+            cnf.clause(&clause);
+            cnf.count_between(&covering[c].clone(), 0, 1);
+        }
+
+        // Edge clues: the first filled cell seen from the edge has the clued
+        // digit or is covered by the clued class, or the filled cells of the
+        // line have the clued sum, product or GCD
+        for clue in puzzle.clues.iter() {
+            let line:Vec<usize> = clue.cells(dim).iter().map(index).collect();
+            let line_filled:Vec<i32> = line.iter().map(|c| filled[*c]).collect();
+            let line_digits:Vec<Vec<i32>> = line.iter().map(|c| cell_digit[*c].clone()).collect();
+            match clue.value {
+                ClueValue::Sum(n) => fold_line(&mut cnf, &line_filled, &line_digits, 0, n, |sum, d| Some(sum + d).filter(|sum| *sum <= n)),
+                ClueValue::Product(n) => fold_line(&mut cnf, &line_filled, &line_digits, 1, n, |product, d| Some(product * d).filter(|product| n.is_multiple_of(*product))),
+                ClueValue::Gcd(n) => fold_line(&mut cnf, &line_filled, &line_digits, 0, n, |divisor, d| Some(gcd(divisor, d)).filter(|divisor| divisor.is_multiple_of(n))),
+                ClueValue::Digit(_) | ClueValue::Pentomino(_) => ()
+            }
+            if !clue.value.is_first_seen() {
+                continue;
+            }
+            let mut seen = -cnf.truth();
+            for c in line.iter() {
+                let mut clause = vec![-filled[*c], seen];
+                match &clue.value {
+                    ClueValue::Digit(d) => clause.push(hook_number[*c][*d]),
+                    ClueValue::Pentomino(class) => {
+                        let cell_mask = Bitboard::from_coord(&Coord::new((c % dim) as isize, (c / dim) as isize), dim);
+                        clause.extend(placements.iter().filter(|(p, _)| shapes.class(p) == class && shapes.mask(p).intersects(&cell_mask)).map(|(_, v)| *v));
+                    },
+                    ClueValue::Sum(_) | ClueValue::Product(_) | ClueValue::Gcd(_) => unreachable!()
+                }
+                cnf.clause(&clause);
+                seen = cnf.or(&[seen, filled[*c]]);
+            }
+            cnf.clause(&[seen]);
+        }
+
+        // Connectivity: every filled cell is reachable from the first filled
+        // cell through filled cells
+        let mut none_before = cnf.truth();
+        let mut reached:Vec<i32> = Vec::new();
+        for c in 0..cells {
+            reached.push(cnf.and(&[none_before, filled[c]]));
+            none_before = cnf.and(&[none_before, -filled[c]]);
+        }
+        for _ in 1..puzzle.occupied_cells() {
+            let mut next:Vec<i32> = Vec::new();
+            for c in 0..cells {
+                let coord = Coord::new((c % dim) as isize, (c / dim) as isize);
+                let neighbors:Vec<i32> = Bitboard::from_coord(&coord, dim).neighbors(dim).coords(dim).iter().map(|n| reached[index(n)]).collect();
+                let any_neighbor = cnf.or(&neighbors);
+                let step = cnf.and(&[filled[c], any_neighbor]);
+                next.push(cnf.or(&[reached[c], step]));
+            }
+            reached = next;
+        }
+        for c in 0..cells {
+            cnf.clause(&[-filled[c], reached[c]]);
+        }
+
+        return HooksCnf{cnf:cnf, squares:squares, orientations:orientations, assignments:assignments, placements:placements};
+    }
+    /// Write the formula in DIMACS CNF format to `path`, and the names of the
+    /// variables that describe the solution to `path.map`.
+    pub fn write_dimacs(&self, path:&str) -> Result<(), String> {
+        let write = |path:&str, contents:&dyn Fn(&mut dyn Write) -> std::io::Result<()>| -> Result<(), String> {
+            let file = match std::fs::File::create(path) {
+                Ok(file) => file,
+                Err(e) => return Err(format!("Could not create '{}': {}", path, e))
+            };
+            let mut writer = std::io::BufWriter::new(file);
+            return contents(&mut writer).and_then(|_| writer.flush()).map_err(|e| format!("Could not write '{}': {}", path, e));
+        };
+        write(path, &|w| {
+            writeln!(w, "c Hooks puzzle, variable names in {}.map", path)?;
+            writeln!(w, "p cnf {} {}", self.cnf.num_vars, self.cnf.clauses.len())?;
+            for clause in self.cnf.clauses.iter() {
+                for lit in clause.iter() {
+                    write!(w, "{} ", lit)?;
+                }
+                writeln!(w, "0")?;
+            }
+            return Ok(());
+        })?;
+        return write(&format!("{}.map", path), &|w| {
+            writeln!(w, "# variable name arguments")?;
+            writeln!(w, "# square S X Y: the square holding the hooks of size S and smaller has top-left cell (X, Y)")?;
+            writeln!(w, "# orientation S O: the corner of the hook of size S points O")?;
+            writeln!(w, "# assign S D: the hook of size S holds the number D")?;
+            writeln!(w, "# filled X Y: cell (X, Y) holds a digit")?;
+            writeln!(w, "# digit X Y D: cell (X, Y) holds the digit D")?;
+            writeln!(w, "# place C X Y ROWS: pentomino C with orientation ROWS has the top-left of its bounding box at (X, Y)")?;
+            for (var, name) in self.cnf.names.iter() {
+                writeln!(w, "{} {}", var, name)?;
+            }
+            return Ok(());
+        });
+    }
+    /// Decode a model, given as the set of true variables, into a solution
+    /// and verify it against the puzzle.
+    pub fn decode(&self, puzzle:&Puzzle, model:&HashSet<i32>) -> Result<HooksSolution, String> {
+        let dim = puzzle.dim;
+        let mut hooks:Vec<Hook> = Vec::new();
+        let mut assignment:Vec<usize> = Vec::new();
+        for s in (1..=dim).rev() {
+            let square = match self.squares[s].iter().find(|(_, v)| model.contains(v)) {
+                Some((square, _)) => *square,
+                None => return Err(format!("The model places no square for the {}x{} hook", s, s))
+            };
+            let orientation = if s == 1 { HookOrientation::NE } else {
+                match self.orientations[s].iter().find(|(_, v)| model.contains(v)) {
+                    Some((orientation, _)) => *orientation,
+                    None => return Err(format!("The model gives no orientation for the {}x{} hook", s, s))
+                }
+            };
+            let (corner, _) = hook_in_square(s, &square, orientation);
+            hooks.push(Hook::new(s, corner, orientation));
+            match (1..=dim).find(|d| model.contains(&self.assignments[s][*d])) {
+                Some(d) => assignment.push(d),
+                None => return Err(format!("The model assigns no number to the {}x{} hook", s, s))
+            }
+        }
+        let mut pentominos:Vec<Placement> = self.placements.iter().filter(|(_, v)| model.contains(v)).map(|(p, _)| *p).collect();
+        grid::pentominos_sort(puzzle, &mut pentominos);
+        let unchecked = HooksSolution{hooks:hooks, assignment:assignment, pentominos:pentominos, shapes:puzzle.shapes, digits:Vec::new(), sums:Vec::new(), empty_regions:Vec::new(), answer:0};
+        verify_solution(puzzle, &unchecked)?;
+        return Ok(HooksSolution::new(puzzle, &unchecked.hooks, &unchecked.assignment, &unchecked.pentominos));
+    }
+}
+
+/// Read the true variables of a SAT solver's model. Accepts DIMACS solver
+/// output with `s` and `v` lines, or a plain list of literals.
+pub fn read_model(path:&str) -> Result<HashSet<i32>, String> {
+    let input = match std::fs::read_to_string(path) {
+        Ok(input) => input,
+        Err(e) => return Err(format!("Could not read model file '{}': {}", path, e))
+    };
+    let mut model:HashSet<i32> = HashSet::new();
+    for line in input.lines() {
+        let line = line.trim();
+        if line.starts_with('c') {
+            continue;
+        }
+        if line.starts_with('s') || line.contains("SAT") {
+            if line.contains("UNSAT") {
+                return Err(format!("The model file '{}' reports the formula unsatisfiable", path));
+            }
+            continue;
+        }
+        for token in line.trim_start_matches('v').split_whitespace() {
+            match token.parse::<i32>() {
+                Ok(lit) if lit > 0 => { model.insert(lit); },
+                Ok(_) => (),
+                Err(_) => return Err(format!("Could not parse literal '{}' in model file '{}'", token, path))
+            }
+        }
+    }
+    return Ok(model);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HooksCnf;
+    use crate::constraint::gcd;
+    use crate::grid::pentomino_classes;
+    use crate::puzzle::{ClueValue, EdgeClue, Puzzle, Side, SEPTEMBER_PUZZLE};
+    use std::collections::HashSet;
+
+    /// Variables describing the solution of the September 2025 puzzle, as
+    /// named in the map file
+    const SEPTEMBER_MODEL:[&str; 35] = [
+        "square 9 0 0", "square 8 0 0", "square 7 0 0", "square 6 0 0", "square 5 0 1", "square 4 1 2", "square 3 2 3", "square 2 3 4", "square 1 4 4",
+        "orientation 9 SE", "orientation 8 SE", "orientation 7 SE", "orientation 6 NE", "orientation 5 NW", "orientation 4 NW", "orientation 3 NW", "orientation 2 SW",
+        "assign 9 9", "assign 8 8", "assign 7 7", "assign 6 5", "assign 5 4", "assign 4 6", "assign 3 3", "assign 2 2", "assign 1 1",
+        "place I 1 0 #####", "place U 6 0 #.#/###", "place Z 4 6 ..#/###/#..", "place V 6 6 ..#/..#/###", "place X 6 2 .#./###/.#.",
+        "place N 0 4 #./#./##/.#", "place F 2 1 .#./###/..#", "place L 1 1 #./#./#./##", "place T 2 4 ..#/###/..#"
+    ];
+
+    /// Return the model setting the named variables of the encoding true.
+    fn model(encoding:&HooksCnf, names:&[&str]) -> HashSet<i32> {
+        return names.iter().map(|name| encoding.cnf.names.iter().find(|(_, n)| n == name).unwrap_or_else(|| panic!("No variable {}", name)).0).collect();
+    }
+
+    /// Extend an assignment of some variables by unit propagation, returning
+    /// false if a clause is falsified.
+    fn propagate(encoding:&HooksCnf, values:&mut [Option<bool>]) -> bool {
+        let clauses = &encoding.cnf.clauses;
+        let mut occurrences:Vec<Vec<usize>> = vec![Vec::new(); values.len()];
+        for (i, clause) in clauses.iter().enumerate() {
+            for lit in clause.iter() {
+                occurrences[lit.unsigned_abs() as usize].push(i);
+            }
+        }
+        let value = |values:&[Option<bool>], lit:i32| values[lit.unsigned_abs() as usize].map(|v| v == (lit > 0));
+        let mut pending:Vec<usize> = (0..clauses.len()).collect();
+        while let Some(i) = pending.pop() {
+            let clause = &clauses[i];
+            if clause.iter().any(|lit| value(values, *lit) == Some(true)) {
+                continue;
+            }
+            let open:Vec<i32> = clause.iter().copied().filter(|lit| value(values, *lit).is_none()).collect();
+            match open.len() {
+                0 => return false,
+                1 => {
+                    let var = open[0].unsigned_abs() as usize;
+                    values[var] = Some(open[0] > 0);
+                    pending.extend(occurrences[var].iter().copied());
+                },
+                _ => {}
+            }
+        }
+        return true;
+    }
+
+    /// Return the value of every named variable of the encoding for the
+    /// September 2025 solution with the given digits, leaving the auxiliary
+    /// variables unset.
+    fn solution_values(encoding:&HooksCnf, digits:&[Vec<Option<usize>>]) -> Vec<Option<bool>> {
+        let mut names:Vec<String> = SEPTEMBER_MODEL.iter().map(|name| name.to_string()).collect();
+        for (y, row) in digits.iter().enumerate() {
+            for (x, digit) in row.iter().enumerate() {
+                if let Some(d) = digit {
+                    names.push(format!("filled {} {}", x, y));
+                    names.push(format!("digit {} {} {}", x, y, d));
+                }
+            }
+        }
+        let mut values:Vec<Option<bool>> = vec![None; encoding.cnf.num_vars + 1];
+        for (var, name) in encoding.cnf.names.iter() {
+            values[*var as usize] = Some(names.contains(name));
+        }
+        return values;
+    }
+
+    #[test]
+    fn decodes_the_september_solution() {
+        let puzzle = Puzzle::parse(SEPTEMBER_PUZZLE).unwrap();
+        let encoding = HooksCnf::new(&puzzle, &pentomino_classes(&puzzle));
+        let solution = encoding.decode(&puzzle, &model(&encoding, &SEPTEMBER_MODEL)).unwrap();
+        assert_eq!(solution.answer, 1620);
+        // Fixing every named variable to the solution determines the
+        // auxiliary variables, and satisfies every clause
+        let mut values = solution_values(&encoding, &solution.digits);
+        assert!(propagate(&encoding, &mut values));
+        assert!(values.iter().skip(1).all(|v| v.is_some()));
+    }
+
+    #[test]
+    fn encodes_line_clues() {
+        let september = Puzzle::parse(SEPTEMBER_PUZZLE).unwrap();
+        let encoding = HooksCnf::new(&september, &pentomino_classes(&september));
+        let digits = encoding.decode(&september, &model(&encoding, &SEPTEMBER_MODEL)).unwrap().digits;
+        // Row 2 holds 6 6 6 6 8, so its GCD is 2
+        let line:Vec<usize> = digits[2].iter().flatten().copied().collect();
+        let (sum, product, divisor) = (line.iter().sum::<usize>(), line.iter().product::<usize>(), line.iter().copied().fold(0, gcd));
+        assert_eq!(divisor, 2);
+        let clues = [(ClueValue::Sum(sum), true), (ClueValue::Sum(sum + 1), false), (ClueValue::Sum(sum - 1), false),
+            (ClueValue::Product(product), true), (ClueValue::Product(product / 2), false), (ClueValue::Product(product * 3), false),
+            (ClueValue::Gcd(divisor), true), (ClueValue::Gcd(1), false), (ClueValue::Gcd(6), false)];
+        for (value, satisfied) in clues {
+            for side in [Side::Left, Side::Right] {
+                let mut clues = september.clues.clone();
+                clues.push(EdgeClue{side:side, line:2, value:value.clone()});
+                let puzzle = Puzzle::new(september.dim, september.givens.clone(), clues);
+                let encoding = HooksCnf::new(&puzzle, &pentomino_classes(&puzzle));
+                let mut values = solution_values(&encoding, &digits);
+                assert_eq!(propagate(&encoding, &mut values), satisfied, "{} {}", side, value);
+                assert!(!satisfied || values.iter().skip(1).all(|v| v.is_some()));
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_a_model_missing_a_pentomino() {
+        let puzzle = Puzzle::parse(SEPTEMBER_PUZZLE).unwrap();
+        let encoding = HooksCnf::new(&puzzle, &pentomino_classes(&puzzle));
+        let names:Vec<&str> = SEPTEMBER_MODEL.iter().copied().filter(|name| !name.starts_with("place T")).collect();
+        assert!(encoding.decode(&puzzle, &model(&encoding, &names)).is_err());
+    }
+}
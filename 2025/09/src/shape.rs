@@ -0,0 +1,146 @@
+use crate::bitboard::Bitboard;
+use crate::grid::Coord;
+use crate::polyomino::{free_polyominos, Polyomino};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Mutex, OnceLock};
+
+/// Shape tables already built, by grid dimension and piece size
+static SHAPE_TABLES:OnceLock<Mutex<HashMap<(usize, usize), &'static ShapeTable>>> = OnceLock::new();
+
+/// Index of a shape in `ShapeTable::shapes`
+pub type ShapeId = usize;
+/// Index of an orientation in `ShapeTable::orientations`
+pub type OrientationId = u16;
+
+/// A piece shape: a free polyomino with the range of its orientations in the
+/// table.
+#[derive(Debug)]
+pub struct Shape {
+    pub polyomino:&'static Polyomino,
+    pub orientations:Range<usize>
+}
+
+/// One orientation of a shape, with its cell mask at every position of the
+/// grid.
+#[derive(Debug)]
+pub struct Orientation {
+    pub shape:ShapeId,
+    /// Cells with the bounding box at the origin, in row-major order
+    pub cells:Vec<Coord>,
+    pub width:usize,
+    pub height:usize,
+    /// Rows of the bounding box, with `#` for cells of the piece and `.` for
+    /// its other cells
+    pub rows:Vec<String>,
+    /// Cells covered with the bounding box at each cell of the grid, indexed
+    /// `y * dim + x`, or no cells where the orientation leaves the grid
+    masks:Vec<Bitboard>
+}
+
+/// A piece placed on the grid: an orientation from a `ShapeTable` with the
+/// upper-left cell of its bounding box at (`x`, `y`).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct Placement {
+    pub orientation_id:OrientationId,
+    pub x:u8,
+    pub y:u8
+}
+impl Placement {
+    pub fn position(&self) -> Coord {
+        return Coord::new(self.x as isize, self.y as isize);
+    }
+}
+
+/// Every shape of a piece size and its distinct orientations, with cell
+/// masks for a `dim` x `dim` grid. Tables are interned, so placements from
+/// any puzzle with the same grid dimension and piece size index the same
+/// table.
+#[derive(Debug)]
+pub struct ShapeTable {
+    pub dim:usize,
+    pub piece_size:usize,
+    /// Shapes in the order of `free_polyominos`, alphabetical by name
+    pub shapes:Vec<Shape>,
+    /// Orientations of each shape in turn, ordered by their rows
+    pub orientations:Vec<Orientation>
+}
+impl ShapeTable {
+    /// Return the table for a grid dimension and piece size, building it on
+    /// first use.
+    pub fn get(dim:usize, piece_size:usize) -> &'static ShapeTable {
+        let mut cache = SHAPE_TABLES.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap();
+        return cache.entry((dim, piece_size)).or_insert_with(|| Box::leak(Box::new(ShapeTable::new(dim, piece_size))));
+    }
+    fn new(dim:usize, piece_size:usize) -> ShapeTable {
+        let mut shapes:Vec<Shape> = Vec::new();
+        let mut orientations:Vec<Orientation> = Vec::new();
+        for polyomino in free_polyominos(piece_size) {
+            let start = orientations.len();
+            let mut shape_orientations:Vec<Orientation> = polyomino.orientations().into_iter().map(|cells| {
+                let width = cells.iter().map(|c| c.x as usize + 1).max().unwrap_or(0);
+                let height = cells.iter().map(|c| c.y as usize + 1).max().unwrap_or(0);
+                let rows = (0..height).map(|y| (0..width).map(|x| if cells.contains(&Coord::new(x as isize, y as isize)) { '#' } else { '.' }).collect()).collect();
+                let base = if width <= dim && height <= dim { Bitboard::from_coords(cells.iter(), dim) } else { None };
+                let mut masks = vec![Bitboard::EMPTY; dim * dim];
+                if let Some(base) = base {
+                    for y in 0..=dim - height {
+                        for x in 0..=dim - width {
+                            masks[y * dim + x] = base.translate(x, y, dim);
+                        }
+                    }
+                }
+                Orientation{shape:shapes.len(), cells:cells, width:width, height:height, rows:rows, masks:masks}
+            }).collect();
+            shape_orientations.sort_by(|a, b| a.rows.cmp(&b.rows));
+            orientations.extend(shape_orientations);
+            shapes.push(Shape{polyomino:polyomino, orientations:start..orientations.len()});
+        }
+        assert!(orientations.len() <= OrientationId::MAX as usize + 1, "Too many orientations of {}-cell pieces", piece_size);
+        return ShapeTable{dim:dim, piece_size:piece_size, shapes:shapes, orientations:orientations};
+    }
+    /// Return the shape with the given name.
+    pub fn shape_id(&self, name:&str) -> Option<ShapeId> {
+        return self.shapes.iter().position(|shape| shape.polyomino.name == name);
+    }
+    pub fn name(&self, shape:ShapeId) -> &'static str {
+        return self.shapes[shape].polyomino.name;
+    }
+    /// Return the orientation of a shape with the given cells, which must be
+    /// normalized as by `polyomino::parse_shape`.
+    pub fn find_orientation(&self, shape:ShapeId, cells:&[Coord]) -> Option<OrientationId> {
+        return self.shapes[shape].orientations.clone().find(|o| self.orientations[*o].cells == cells).map(|o| o as OrientationId);
+    }
+    /// Return the placement of an orientation at a position, if it lies
+    /// within the grid.
+    pub fn placement(&self, orientation_id:OrientationId, position:&Coord) -> Option<Placement> {
+        let orientation = &self.orientations[orientation_id as usize];
+        if position.x < 0 || position.y < 0 || position.x as usize + orientation.width > self.dim || position.y as usize + orientation.height > self.dim {
+            return None;
+        }
+        return Some(Placement{orientation_id:orientation_id, x:position.x as u8, y:position.y as u8});
+    }
+    /// Return every placement of a shape within the grid, by orientation and
+    /// then in row-major order of position.
+    pub fn placements(&self, shape:ShapeId) -> impl Iterator<Item = Placement> + '_ {
+        let dim = self.dim;
+        return self.shapes[shape].orientations.clone().flat_map(move |o| {
+            let masks = &self.orientations[o].masks;
+            (0..dim * dim).filter(move |i| !masks[*i].is_empty()).map(move |i| Placement{orientation_id:o as OrientationId, x:(i % dim) as u8, y:(i / dim) as u8})
+        });
+    }
+    pub fn orientation(&self, placement:&Placement) -> &Orientation {
+        return &self.orientations[placement.orientation_id as usize];
+    }
+    pub fn shape(&self, placement:&Placement) -> ShapeId {
+        return self.orientation(placement).shape;
+    }
+    /// Return the name of the shape of a placement, such as `F`.
+    pub fn class(&self, placement:&Placement) -> &'static str {
+        return self.name(self.shape(placement));
+    }
+    /// Return the cells covered by a placement.
+    pub fn mask(&self, placement:&Placement) -> Bitboard {
+        return self.orientation(placement).masks[placement.y as usize * self.dim + placement.x as usize];
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use crate::constraint::SUM_MODULUS;
+use crate::grid::{hook_layout_hash, Hook};
+use crate::tree::{TreeNode, TreePosition};
+
+/// A rule that can reject a pentomino placement.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Rule {
+    /// The placement overlaps another pentomino or leaves the grid
+    Overlap,
+    /// The class is already placed
+    RepeatedClass,
+    /// The digits in the pentomino do not sum to a multiple of `SUM_MODULUS`
+    SumModulus,
+    /// A digit appears more times than its value
+    DigitOvercount,
+    /// A clued class is not the first pentomino seen from its clue's edge, a
+    /// digit clue is not the first digit seen, or the filled cells of a line
+    /// cannot have its clued sum, product or GCD
+    EdgeClue,
+    /// A 2x2 block is completely filled
+    TwoByTwo,
+    /// A complete arrangement holds a digit fewer times than its value
+    DigitCount,
+    /// A complete arrangement leaves a given digit empty
+    Given,
+    /// A complete arrangement is not connected
+    Connectivity,
+    /// The filled cells of the cell search cannot be divided into pieces
+    Division
+}
+
+/// Every rule, in the order they are reported.
+pub const RULES:[Rule; 10] = [Rule::Overlap, Rule::RepeatedClass, Rule::SumModulus, Rule::DigitOvercount, Rule::EdgeClue, Rule::TwoByTwo, Rule::DigitCount, Rule::Given, Rule::Connectivity, Rule::Division];
+
+impl Rule {
+    /// Position of the rule in `RULES`.
+    pub fn index(&self) -> usize {
+        return *self as usize;
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::Overlap => "overlap",
+            Rule::RepeatedClass => "repeated class",
+            Rule::SumModulus => "sum modulus",
+            Rule::DigitOvercount => "digit overcount",
+            Rule::EdgeClue => "edge clue",
+            Rule::TwoByTwo => "2x2",
+            Rule::DigitCount => "digit count",
+            Rule::Given => "given",
+            Rule::Connectivity => "connectivity",
+            Rule::Division => "division"
+        }
+    }
+}
+
+/// Number of placements rejected by each rule, indexed by position in
+/// `RULES`.
+pub type Rejections = [usize; RULES.len()];
+
+/// Counts gathered by the pentomino search. Each worker thread gathers its
+/// own counts, which are merged when it finishes.
+#[derive(Debug, Clone, Default)]
+pub struct SearchStats {
+    /// Nodes visited with each number of pentominos placed
+    pub nodes_per_depth:Vec<usize>,
+    /// Nodes visited by the class of the pentomino placed last
+    pub nodes_per_class:BTreeMap<String, usize>,
+    /// Placements rejected by each rule, per hook/number combination
+    pub rejections:BTreeMap<usize, Rejections>,
+    /// Nodes visited by the cell search
+    pub cell_nodes:usize,
+    /// Tree explored by the search, if `SearchOptions::tree_depth` is set
+    pub tree:Option<TreeNode>,
+    /// Work done on each hook/number combination the search visited
+    pub combinations:BTreeMap<usize, CombinationStats>
+}
+
+/// Work done by the pentomino search on one hook/number combination.
+#[derive(Debug, Clone, Default)]
+pub struct CombinationStats {
+    /// Prefix tasks the combination was expanded into
+    pub prefixes:usize,
+    /// Nodes visited, counted as in `nodes_per_depth` and `cell_nodes`
+    pub nodes:usize,
+    /// Time worker threads spent on the combination's tasks, summed
+    pub cpu_time:Duration,
+    /// When the first of the combination's tasks started and the last one
+    /// finished
+    pub start:Option<Instant>,
+    pub end:Option<Instant>,
+    pub solutions:usize
+}
+
+impl CombinationStats {
+    /// Return the time from the start of the combination's first task to the
+    /// end of its last.
+    pub fn wall_time(&self) -> Duration {
+        return match (self.start, self.end) {
+            (Some(start), Some(end)) => end - start,
+            _ => Duration::ZERO
+        };
+    }
+    fn merge(&mut self, other:&CombinationStats) {
+        self.prefixes += other.prefixes;
+        self.nodes += other.nodes;
+        self.cpu_time += other.cpu_time;
+        self.start = self.start.into_iter().chain(other.start).min();
+        self.end = self.end.into_iter().chain(other.end).max();
+        self.solutions += other.solutions;
+    }
+}
+
+/// A row of the per-combination report.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CombinationReportJson {
+    pub combination:usize,
+    /// Hash of the hook layout from `hook_layout_hash`, in hexadecimal
+    pub hooks:String,
+    pub assignment:Vec<usize>,
+    pub prefixes:usize,
+    pub nodes:usize,
+    pub wall_seconds:f64,
+    pub cpu_seconds:f64,
+    pub solutions:usize
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatsJson {
+    /// Nodes visited with each number of pentominos placed, starting from 1
+    pub nodes_per_depth:Vec<usize>,
+    pub nodes_per_class:BTreeMap<String, usize>,
+    /// Placements rejected by each rule, for the combinations with any
+    /// rejections
+    pub rejections:Vec<CombinationRejectionsJson>,
+    pub total_rejections:BTreeMap<String, usize>,
+    /// Nodes visited by the cell search, written with `--strategy cells`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cell_nodes:Option<usize>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CombinationRejectionsJson {
+    pub combination:usize,
+    pub rules:BTreeMap<String, usize>
+}
+
+pub fn combination_report_to_json(rows:&[CombinationReportJson]) -> String {
+    return serde_json::to_string_pretty(rows).unwrap();
+}
+
+/// Write report rows as CSV with a header line. The numbers of an
+/// assignment are separated by spaces.
+pub fn combination_report_to_csv(rows:&[CombinationReportJson]) -> String {
+    let mut csv = String::from("combination,hooks,assignment,prefixes,nodes,wall_seconds,cpu_seconds,solutions\n");
+    for row in rows.iter() {
+        let assignment:Vec<String> = row.assignment.iter().map(|n| n.to_string()).collect();
+        csv.push_str(&format!("{},{},{},{},{},{:.6},{:.6},{}\n", row.combination, row.hooks, assignment.join(" "), row.prefixes, row.nodes, row.wall_seconds, row.cpu_seconds, row.solutions));
+    }
+    return csv;
+}
+
+fn rule_map(rejections:&Rejections) -> BTreeMap<String, usize> {
+    return RULES.iter().zip(rejections.iter()).map(|(rule, n)| (rule.name().to_string(), *n)).collect();
+}
+
+impl SearchStats {
+    /// Count a node reached by placing a pentomino of `class`, leaving
+    /// `depth` pentominos placed.
+    pub fn node(&mut self, depth:usize, class:&str) {
+        if self.nodes_per_depth.len() <= depth {
+            self.nodes_per_depth.resize(depth + 1, 0);
+        }
+        self.nodes_per_depth[depth] += 1;
+        match self.nodes_per_class.get_mut(class) {
+            Some(n) => *n += 1,
+            None => {
+                self.nodes_per_class.insert(class.to_string(), 1);
+            }
+        }
+    }
+    /// Add rejection counts for a combination.
+    pub fn reject(&mut self, combination:usize, rejections:&Rejections) {
+        if rejections.iter().all(|n| *n == 0) {
+            return;
+        }
+        let counts = self.rejections.entry(combination).or_insert([0; RULES.len()]);
+        for (count, n) in counts.iter_mut().zip(rejections.iter()) {
+            *count += n;
+        }
+    }
+    /// Add the counts gathered by another worker.
+    pub fn merge(&mut self, other:&SearchStats) {
+        for (depth, n) in other.nodes_per_depth.iter().enumerate() {
+            if self.nodes_per_depth.len() <= depth {
+                self.nodes_per_depth.resize(depth + 1, 0);
+            }
+            self.nodes_per_depth[depth] += n;
+        }
+        for (class, n) in other.nodes_per_class.iter() {
+            *self.nodes_per_class.entry(class.clone()).or_insert(0) += n;
+        }
+        for (combination, rejections) in other.rejections.iter() {
+            self.reject(*combination, rejections);
+        }
+        self.cell_nodes += other.cell_nodes;
+        for (combination, counts) in other.combinations.iter() {
+            self.combinations.entry(*combination).or_default().merge(counts);
+        }
+        if let Some(tree) = &other.tree {
+            match &mut self.tree {
+                Some(t) => t.merge(tree.clone()),
+                None => self.tree = Some(tree.clone())
+            }
+        }
+    }
+    /// Return the nodes visited so far by either strategy.
+    pub fn total_nodes(&self) -> usize {
+        return self.nodes_per_depth.iter().sum::<usize>() + self.cell_nodes;
+    }
+    /// Count a task of a combination that started at `start`, when
+    /// `total_nodes` was `nodes_before`.
+    pub fn task(&mut self, combination:usize, start:Instant, nodes_before:usize) {
+        let nodes = self.total_nodes() - nodes_before;
+        let end = Instant::now();
+        let counts = self.combinations.entry(combination).or_default();
+        counts.nodes += nodes;
+        counts.cpu_time += end - start;
+        counts.start = Some(counts.start.map_or(start, |s| s.min(start)));
+        counts.end = Some(counts.end.map_or(end, |e| e.max(end)));
+    }
+    /// Return a report row for every combination, with zero counts for those
+    /// the search did not visit.
+    pub fn combination_report(&self, combinations:&[(Vec<Hook>, Vec<usize>)]) -> Vec<CombinationReportJson> {
+        return combinations.iter().enumerate().map(|(i, (hooks, assignment))| {
+            let counts = self.combinations.get(&i).cloned().unwrap_or_default();
+            CombinationReportJson{
+                combination:i,
+                hooks:format!("{:016x}", hook_layout_hash(hooks)),
+                assignment:assignment.clone(),
+                prefixes:counts.prefixes,
+                nodes:counts.nodes,
+                wall_seconds:counts.wall_time().as_secs_f64(),
+                cpu_seconds:counts.cpu_time.as_secs_f64(),
+                solutions:counts.solutions
+            }
+        }).collect();
+    }
+    /// Add a subtree recorded by a worker to the search tree.
+    pub fn graft_tree(&mut self, position:&TreePosition, node:TreeNode) {
+        if let Some(tree) = &mut self.tree {
+            tree.graft(&position.path, node);
+        }
+    }
+    /// Return the rejections by each rule summed over every combination.
+    pub fn total_rejections(&self) -> Rejections {
+        let mut total = [0; RULES.len()];
+        for rejections in self.rejections.values() {
+            for (count, n) in total.iter_mut().zip(rejections.iter()) {
+                *count += n;
+            }
+        }
+        return total;
+    }
+    pub fn to_json(&self) -> StatsJson {
+        return StatsJson{
+            nodes_per_depth:self.nodes_per_depth.iter().skip(1).copied().collect(),
+            nodes_per_class:self.nodes_per_class.clone(),
+            rejections:self.rejections.iter().map(|(combination, rejections)| CombinationRejectionsJson{combination:*combination, rules:rule_map(rejections)}).collect(),
+            total_rejections:rule_map(&self.total_rejections()),
+            cell_nodes:if self.cell_nodes > 0 { Some(self.cell_nodes) } else { None }
+        };
+    }
+    /// Print the counts as tables.
+    pub fn print(&self) {
+        if self.cell_nodes > 0 {
+            println!("\nCell search nodes: {}", self.cell_nodes);
+        }
+        println!("\nNodes per depth:");
+        println!("{:>6} {:>12}", "depth", "nodes");
+        for (depth, n) in self.nodes_per_depth.iter().enumerate().skip(1) {
+            println!("{:>6} {:>12}", depth, n);
+        }
+        println!("\nNodes per class:");
+        println!("{:>6} {:>12}", "class", "nodes");
+        for (class, n) in self.nodes_per_class.iter() {
+            println!("{:>6} {:>12}", class, n);
+        }
+        println!("\nRejections per rule (sum modulus {}):", SUM_MODULUS);
+        let header:Vec<String> = RULES.iter().map(|rule| format!("{:>15}", rule.name())).collect();
+        println!("{:>11} {}", "combination", header.join(" "));
+        for (combination, rejections) in self.rejections.iter() {
+            let counts:Vec<String> = rejections.iter().map(|n| format!("{:>15}", n)).collect();
+            println!("{:>11} {}", combination, counts.join(" "));
+        }
+        let counts:Vec<String> = self.total_rejections().iter().map(|n| format!("{:>15}", n)).collect();
+        println!("{:>11} {}", "total", counts.join(" "));
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+use std::time::Instant;
+use serde::Serialize;
+
+/// A node of a recorded search tree, with counts for its whole subtree.
+/// Levels below the recording depth are not kept as nodes: their counts are
+/// added to their deepest recorded ancestor.
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeNode {
+    /// Decision that leads to the node
+    pub label:String,
+    /// Nodes visited in the subtree. The root of a stage is not counted.
+    pub nodes:usize,
+    /// Time spent in the subtree, summed over worker threads
+    pub seconds:f64,
+    pub solutions:usize,
+    /// Rule that pruned the node, if it was refuted as soon as it was reached
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule:Option<&'static str>,
+    /// Nodes and placements pruned in the subtree, by rule
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub pruned:BTreeMap<&'static str, usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children:Vec<TreeNode>
+}
+
+impl TreeNode {
+    pub fn new(label:String) -> TreeNode {
+        return TreeNode{label:label, nodes:0, seconds:0.0, solutions:0, rule:None, pruned:BTreeMap::new(), children:Vec::new()};
+    }
+    /// Add the subtree counts of another node, without its children.
+    fn add_counts(&mut self, other:&TreeNode) {
+        self.nodes += other.nodes;
+        self.seconds += other.seconds;
+        self.solutions += other.solutions;
+        for (rule, n) in other.pruned.iter() {
+            *self.pruned.entry(rule).or_insert(0) += n;
+        }
+    }
+    /// Merge another recording of the same node, matching children by label.
+    pub fn merge(&mut self, other:TreeNode) {
+        self.add_counts(&other);
+        self.rule = self.rule.or(other.rule);
+        for child in other.children {
+            match self.children.iter_mut().find(|c| c.label == child.label) {
+                Some(c) => c.merge(child),
+                None => self.children.push(child)
+            }
+        }
+    }
+    /// Merge a subtree recorded elsewhere into the node at `path` below this
+    /// one, adding its counts to every node on the way.
+    pub fn graft(&mut self, path:&[String], node:TreeNode) {
+        let mut current = self;
+        for label in path.iter() {
+            current.add_counts(&node);
+            let i = match current.children.iter().position(|c| c.label == *label) {
+                Some(i) => i,
+                None => {
+                    current.children.push(TreeNode::new(label.clone()));
+                    current.children.len() - 1
+                }
+            };
+            current = &mut current.children[i];
+        }
+        current.merge(node);
+    }
+    /// Write the node and its subtree as DOT statements, numbering the nodes
+    /// from `next_id`.
+    fn write_dot(&self, out:&mut String, next_id:&mut usize) -> usize {
+        let id = *next_id;
+        *next_id += 1;
+        let mut lines = vec![self.label.clone(), format!("{} nodes, {:.3} s", self.nodes, self.seconds)];
+        if self.solutions > 0 {
+            lines.push(format!("{} solutions", self.solutions));
+        }
+        if let Some(rule) = self.rule {
+            lines.push(format!("pruned: {}", rule));
+        }
+        else if !self.pruned.is_empty() {
+            let pruned:Vec<String> = self.pruned.iter().map(|(rule, n)| format!("{} {}", rule, n)).collect();
+            lines.push(format!("pruned: {}", pruned.join(", ")));
+        }
+        let label = lines.iter().map(|line| line.replace('\\', "\\\\").replace('"', "\\\"")).collect::<Vec<String>>().join("\\n");
+        let color = if self.rule.is_some() { ", color=red" } else if self.solutions > 0 { ", color=darkgreen" } else { "" };
+        out.push_str(&format!("  n{} [label=\"{}\"{}];\n", id, label, color));
+        for child in self.children.iter() {
+            let child_id = child.write_dot(out, next_id);
+            out.push_str(&format!("  n{} -> n{};\n", id, child_id));
+        }
+        return id;
+    }
+}
+
+/// Where a subtree of a recorded tree starts: the labels of its recorded
+/// ancestors below the stage root, ending with the subtree root itself if it
+/// is recorded, and the depth of the subtree root below the stage root.
+#[derive(Debug, Clone, Default)]
+pub struct TreePosition {
+    pub path:Vec<String>,
+    pub depth:usize
+}
+
+/// Records the tree explored by a DFS as it enters and leaves nodes, down to
+/// a maximum depth below the stage root. A disabled recorder ignores every
+/// call, so searches can take one unconditionally.
+pub struct TreeRecorder {
+    /// Deepest level kept as separate nodes, or `None` if recording is off
+    max_depth:Option<usize>,
+    /// Recorded nodes entered and not yet left, starting with the root, with
+    /// the time each was entered
+    stack:Vec<(TreeNode, Instant)>,
+    /// Labels of the recorded ancestors of the root
+    path:Vec<String>,
+    /// Depth of the root below the stage root
+    root_depth:usize,
+    /// Nodes entered below the deepest recorded node and not yet left
+    hidden:usize
+}
+
+impl TreeRecorder {
+    /// Return a recorder that records nothing.
+    pub fn disabled() -> TreeRecorder {
+        return TreeRecorder{max_depth:None, stack:Vec::new(), path:Vec::new(), root_depth:0, hidden:0};
+    }
+    /// Return a recorder for the subtree rooted at a stage root labelled
+    /// `label`.
+    pub fn new(label:&str, max_depth:Option<usize>) -> TreeRecorder {
+        let mut recorder = TreeRecorder::disabled();
+        if max_depth.is_some() {
+            recorder.max_depth = max_depth;
+            recorder.stack.push((TreeNode::new(label.to_string()), Instant::now()));
+        }
+        return recorder;
+    }
+    /// Return a recorder for the subtree at `position`, whose root has
+    /// already been counted where the position was taken. If the root is
+    /// below the recording depth, the subtree is recorded into its deepest
+    /// recorded ancestor.
+    pub fn at(position:&TreePosition, max_depth:Option<usize>) -> TreeRecorder {
+        let mut recorder = TreeRecorder::new(position.path.last().map_or("", |label| label.as_str()), max_depth);
+        if recorder.max_depth.is_some() {
+            recorder.path = position.path[..position.path.len().saturating_sub(1)].to_vec();
+            recorder.root_depth = position.path.len();
+            recorder.hidden = position.depth - position.path.len();
+        }
+        return recorder;
+    }
+    /// Return the position of the current node.
+    pub fn position(&self) -> TreePosition {
+        let mut path = self.path.clone();
+        if self.root_depth > 0 {
+            path.push(self.stack[0].0.label.clone());
+        }
+        path.extend(self.stack.iter().skip(1).map(|(node, _)| node.label.clone()));
+        return TreePosition{path:path, depth:self.root_depth + self.stack.len().saturating_sub(1) + self.hidden};
+    }
+    /// Enter a child of the current node, labelled by `label` if it is
+    /// recorded as a separate node.
+    pub fn enter(&mut self, label:impl FnOnce() -> String) {
+        let Some(max_depth) = self.max_depth else {
+            return;
+        };
+        if self.hidden > 0 || self.root_depth + self.stack.len() > max_depth {
+            self.hidden += 1;
+            self.stack.last_mut().unwrap().0.nodes += 1;
+            return;
+        }
+        self.stack.push((TreeNode::new(label()), Instant::now()));
+    }
+    /// Leave the current node for its parent.
+    pub fn leave(&mut self) {
+        if self.max_depth.is_none() {
+            return;
+        }
+        if self.hidden > 0 {
+            self.hidden -= 1;
+            return;
+        }
+        let (mut node, start) = self.stack.pop().unwrap();
+        node.nodes += 1;
+        node.seconds = start.elapsed().as_secs_f64();
+        let parent = &mut self.stack.last_mut().unwrap().0;
+        parent.add_counts(&node);
+        parent.children.push(node);
+    }
+    /// Record that the current node was pruned by a rule.
+    pub fn prune(&mut self, rule:&'static str) {
+        self.reject(rule, 1);
+        if self.hidden == 0 && let Some((node, _)) = self.stack.last_mut() {
+            node.rule = Some(rule);
+        }
+    }
+    /// Count placements rejected by a rule at the current node.
+    pub fn reject(&mut self, rule:&'static str, n:usize) {
+        if n == 0 {
+            return;
+        }
+        if let Some((node, _)) = self.stack.last_mut() {
+            *node.pruned.entry(rule).or_insert(0) += n;
+        }
+    }
+    /// Record a solution at the current node.
+    pub fn solution(&mut self) {
+        if let Some((node, _)) = self.stack.last_mut() {
+            node.solutions += 1;
+        }
+    }
+    /// Return the recorded subtree with the position of its root, or `None`
+    /// if recording is off.
+    pub fn finish(mut self) -> Option<(TreePosition, TreeNode)> {
+        let (mut root, start) = self.stack.pop()?;
+        root.seconds = start.elapsed().as_secs_f64();
+        let mut path = self.path;
+        if self.root_depth > 0 {
+            path.push(root.label.clone());
+        }
+        return Some((TreePosition{path:path, depth:self.root_depth}, root));
+    }
+}
+
+/// The trees recorded for the stages of a search. A stage is missing if it
+/// was not run or not recorded.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SearchTree {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hooks:Option<TreeNode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assign:Option<TreeNode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pentominos:Option<TreeNode>
+}
+
+impl SearchTree {
+    pub fn to_json(&self) -> String {
+        return serde_json::to_string_pretty(self).unwrap();
+    }
+    /// Return the trees as a Graphviz digraph, one tree per stage.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph search {\n  node [shape=box, fontsize=10];\n");
+        let mut next_id = 0;
+        for tree in [&self.hooks, &self.assign, &self.pentominos].into_iter().flatten() {
+            tree.write_dot(&mut out, &mut next_id);
+        }
+        out.push_str("}\n");
+        return out;
+    }
+}
@@ -0,0 +1,533 @@
+use std::collections::BTreeSet;
+use crate::constraint::{gcd, SUM_MODULUS};
+use crate::grid::{Coord, Hook, HookOrientation};
+use crate::polyomino::{free_polyominos, piece_noun};
+use crate::puzzle::{ClueValue, Puzzle};
+
+/// Digit in each cell of a grid, indexed `[y][x]`, or `None` for an empty
+/// cell.
+pub type Digits = Vec<Vec<Option<usize>>>;
+
+/// Pentominos dividing the filled cells, as classes with their cells.
+pub type Division = Vec<(String, Vec<Coord>)>;
+
+/// A rule broken by a proposed solution.
+#[derive(Debug, Clone)]
+pub struct Violation {
+    /// Name of the rule
+    pub rule:&'static str,
+    /// Description of the violation, with the cells involved
+    pub message:String
+}
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut  std::fmt::Formatter) ->  std::fmt::Result {
+        write!(f, "{}: {}", self.rule, self.message)
+    }
+}
+
+/// The result of checking a proposed solution against every rule of a
+/// puzzle.
+pub struct GridReport {
+    /// Hook layout the digits were checked against: the given layout, or
+    /// else the layout that fits the digits best
+    pub hooks:Vec<Hook>,
+    /// Pentominos the filled cells were divided into, if any division was
+    /// found
+    pub pentominos:Option<Division>,
+    pub violations:Vec<Violation>,
+    /// Product of the areas of the empty regions
+    pub answer:usize
+}
+
+/// Split a `dim` x `dim` block of whitespace-separated tokens, with `#`
+/// starting a comment. A grid of at most 9 columns may also be written with
+/// one token per row and no spaces.
+fn grid_tokens(input:&str, dim:usize, what:&str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows:Vec<Vec<String>> = Vec::new();
+    for line in input.lines() {
+        let content = match line.find('#') {
+            Some(i) => &line[..i],
+            None => line
+        };
+        let mut tokens:Vec<String> = content.split_whitespace().map(|t| t.to_string()).collect();
+        if tokens.len() == 1 && dim > 1 && dim <= 9 && tokens[0].len() == dim {
+            tokens = tokens[0].chars().map(|c| c.to_string()).collect();
+        }
+        if !tokens.is_empty() {
+            rows.push(tokens);
+        }
+    }
+    if rows.len() != dim {
+        return Err(format!("The {} has {} rows, expected {}", what, rows.len(), dim));
+    }
+    for (y, row) in rows.iter().enumerate() {
+        if row.len() != dim {
+            return Err(format!("Row {} of the {} has {} entries, expected {}", y, what, row.len(), dim));
+        }
+    }
+    return Ok(rows);
+}
+
+/// Parse a proposed solution: a `dim` x `dim` grid where `.` is an empty cell
+/// and a number is the digit in a filled cell.
+pub fn parse_grid(input:&str, dim:usize) -> Result<Digits, String> {
+    let mut digits = vec![vec![None; dim]; dim];
+    for (y, row) in grid_tokens(input, dim, "grid")?.iter().enumerate() {
+        for (x, token) in row.iter().enumerate() {
+            if token == "." {
+                continue;
+            }
+            match token.parse::<usize>() {
+                Ok(d) if (1..=dim).contains(&d) => digits[y][x] = Some(d),
+                _ => return Err(format!("Invalid digit '{}' at ({},{})", token, x, y))
+            }
+        }
+    }
+    return Ok(digits);
+}
+
+/// Parse a hook layout: a `dim` x `dim` grid giving the size of the hook
+/// each cell belongs to, as printed with a solution.
+pub fn parse_hook_layout(input:&str, dim:usize) -> Result<Vec<Vec<usize>>, String> {
+    let mut labels = vec![vec![0; dim]; dim];
+    for (y, row) in grid_tokens(input, dim, "hook layout")?.iter().enumerate() {
+        for (x, token) in row.iter().enumerate() {
+            match token.parse::<usize>() {
+                Ok(k) if (1..=dim).contains(&k) => labels[y][x] = k,
+                _ => return Err(format!("Invalid hook size '{}' at ({},{})", token, x, y))
+            }
+        }
+    }
+    return Ok(labels);
+}
+
+/// Return the hook of size `k` with its corner in the given corner of the
+/// `k` x `k` square with upper-left cell `origin`, and the upper-left cell of
+/// the square left for the smaller hooks.
+fn hook_in_square(origin:Coord, k:usize, orientation:HookOrientation) -> (Hook, Coord) {
+    let last = k as isize - 1;
+    let (corner, next) = match orientation {
+        HookOrientation::NW => (origin, Coord::new(origin.x + 1, origin.y + 1)),
+        HookOrientation::NE => (Coord::new(origin.x + last, origin.y), Coord::new(origin.x, origin.y + 1)),
+        HookOrientation::SW => (Coord::new(origin.x, origin.y + last), Coord::new(origin.x + 1, origin.y)),
+        HookOrientation::SE => (Coord::new(origin.x + last, origin.y + last), origin)
+    };
+    return (Hook::new(k, corner, orientation), next);
+}
+
+const ORIENTATIONS:[HookOrientation; 4] = [HookOrientation::NW, HookOrientation::NE, HookOrientation::SW, HookOrientation::SE];
+
+/// Return the cells of a grid in row-major order.
+fn cells(dim:usize) -> impl Iterator<Item = Coord> {
+    return (0..dim * dim).map(move |i| Coord::new((i % dim) as isize, (i / dim) as isize));
+}
+
+fn digit_at(digits:&Digits, coord:&Coord) -> Option<usize> {
+    return digits[coord.y as usize][coord.x as usize];
+}
+
+fn coord_list(coords:&[Coord]) -> String {
+    return coords.iter().map(|c| c.to_string()).collect::<Vec<String>>().join(", ");
+}
+
+/// Build the hooks of a layout from largest to smallest, each fitted to the
+/// cells labelled with its size, reporting every labelled cell that does not
+/// lie in its hook.
+fn layout_hooks(labels:&[Vec<usize>], dim:usize) -> (Vec<Hook>, Vec<Violation>) {
+    let mut hooks:Vec<Hook> = Vec::new();
+    let mut violations:Vec<Violation> = Vec::new();
+    let mut origin = Coord::new(0, 0);
+    for k in (1..=dim).rev() {
+        let labelled:Vec<Coord> = cells(dim).filter(|c| labels[c.y as usize][c.x as usize] == k).collect();
+        // The hook covering the most labelled cells, with ties going to the
+        // first orientation
+        let (_, (hook, next)) = ORIENTATIONS.iter().map(|o| hook_in_square(origin, k, *o)).enumerate().max_by_key(|(i, (hook, _))| {
+            let hook_cells = hook.cells();
+            (labelled.iter().filter(|c| hook_cells.contains(c)).count(), std::cmp::Reverse(*i))
+        }).unwrap();
+        let hook_cells = hook.cells();
+        let outside:Vec<Coord> = labelled.iter().copied().filter(|c| !hook_cells.contains(c)).collect();
+        if !outside.is_empty() {
+            violations.push(Violation{rule:"hooks", message:format!("cells labelled {} at {} are not part of the {}x{} hook with its corner at {}", k, coord_list(&outside), k, k, hook.position)});
+        }
+        hooks.push(hook);
+        origin = next;
+    }
+    return (hooks, violations);
+}
+
+/// Return the filled cells of a hook with their digits, in row-major order.
+fn hook_digits(hook:&Hook, digits:&Digits) -> Vec<(Coord, usize)> {
+    let mut filled:Vec<(Coord, usize)> = hook.cells().into_iter().filter_map(|c| digit_at(digits, &c).map(|d| (c, d))).collect();
+    filled.sort_by_key(|(c, _)| (c.y, c.x));
+    return filled;
+}
+
+/// Check that each hook holds a single digit, as many times as its value,
+/// and that no digit is split between hooks.
+fn hook_digit_violations(hooks:&[Hook], digits:&Digits) -> Vec<Violation> {
+    let mut violations:Vec<Violation> = Vec::new();
+    let mut holders:Vec<Vec<usize>> = vec![Vec::new(); digits.len() + 1];
+    let mut counts:Vec<(usize, usize, Vec<Coord>)> = Vec::new();
+    for hook in hooks.iter() {
+        let filled = hook_digits(hook, digits);
+        let values:BTreeSet<usize> = filled.iter().map(|(_, d)| *d).collect();
+        for d in values.iter() {
+            holders[*d].push(hook.dim);
+        }
+        if values.len() > 1 {
+            let cells:Vec<String> = filled.iter().map(|(c, d)| format!("{} at {}", d, c)).collect();
+            violations.push(Violation{rule:"hook digits", message:format!("the {}x{} hook holds different digits: {}", hook.dim, hook.dim, cells.join(", "))});
+        }
+        else if let Some(d) = values.first() {
+            counts.push((hook.dim, *d, filled.iter().map(|(c, _)| *c).collect()));
+        }
+    }
+    for (d, hook_dims) in holders.iter().enumerate() {
+        if hook_dims.len() > 1 {
+            let names:Vec<String> = hook_dims.iter().map(|k| format!("{}x{}", k, k)).collect();
+            violations.push(Violation{rule:"hook digits", message:format!("digit {} is split between the {} hooks", d, names.join(", "))});
+        }
+    }
+    for (k, d, coords) in counts {
+        if holders[d].len() == 1 && coords.len() != d {
+            violations.push(Violation{rule:"hook digits", message:format!("the {}x{} hook holds {} copies of {} at {}, expected {}", k, k, coords.len(), d, coord_list(&coords), d)});
+        }
+    }
+    return violations;
+}
+
+/// Search every hook layout for one that fits the digits, returning it or
+/// else the layout with the fewest hook digit violations.
+fn best_layout(digits:&Digits) -> (Vec<Hook>, Vec<Violation>) {
+    let dim = digits.len();
+    let mut best:Option<(Vec<Hook>, Vec<Violation>)> = None;
+    let mut hooks:Vec<Hook> = Vec::new();
+    best_layout_recurse(digits, dim, Coord::new(0, 0), &mut hooks, 0, &mut best);
+    return best.unwrap();
+}
+
+/// Each hook holding different digits is a violation however the smaller
+/// hooks are laid out, so layouts with at least as many as the best layout
+/// found so far are pruned.
+fn best_layout_recurse(digits:&Digits, k:usize, origin:Coord, hooks:&mut Vec<Hook>, mixed:usize, best:&mut Option<(Vec<Hook>, Vec<Violation>)>) {
+    if best.as_ref().is_some_and(|(_, violations)| mixed >= violations.len()) {
+        return;
+    }
+    if k == 0 {
+        let violations = hook_digit_violations(hooks, digits);
+        if best.as_ref().is_none_or(|(_, best_violations)| violations.len() < best_violations.len()) {
+            *best = Some((hooks.clone(), violations));
+        }
+        return;
+    }
+    // The four orientations of the 1x1 hook are the same cell
+    let orientations = if k == 1 { &ORIENTATIONS[..1] } else { &ORIENTATIONS[..] };
+    for orientation in orientations {
+        let (hook, next) = hook_in_square(origin, k, *orientation);
+        let filled = hook_digits(&hook, digits);
+        let is_mixed = filled.iter().any(|(_, d)| *d != filled[0].1);
+        hooks.push(hook);
+        best_layout_recurse(digits, k - 1, next, hooks, mixed + is_mixed as usize, best);
+        hooks.pop();
+    }
+}
+
+/// Return the connected regions of the cells that are filled, or empty if
+/// `filled` is false, each in the order found.
+fn regions(digits:&Digits, filled:bool) -> Vec<Vec<Coord>> {
+    let dim = digits.len();
+    let mut seen = vec![vec![false; dim]; dim];
+    let mut regions:Vec<Vec<Coord>> = Vec::new();
+    for start in cells(dim) {
+        if seen[start.y as usize][start.x as usize] || digit_at(digits, &start).is_some() != filled {
+            continue;
+        }
+        seen[start.y as usize][start.x as usize] = true;
+        let mut region = vec![start];
+        let mut i = 0;
+        while i < region.len() {
+            let cell = region[i];
+            for step in [Coord::new(1, 0), Coord::new(-1, 0), Coord::new(0, 1), Coord::new(0, -1)] {
+                let next = &cell + &step;
+                if next.x < 0 || next.y < 0 || next.x as usize >= dim || next.y as usize >= dim {
+                    continue;
+                }
+                if !seen[next.y as usize][next.x as usize] && digit_at(digits, &next).is_some() == filled {
+                    seen[next.y as usize][next.x as usize] = true;
+                    region.push(next);
+                }
+            }
+            i += 1;
+        }
+        regions.push(region);
+    }
+    return regions;
+}
+
+/// Every distinct orientation of each free polyomino of `piece_size` cells,
+/// with its cells in row-major order.
+fn pentomino_orientations(piece_size:usize) -> Vec<(String, Vec<Coord>)> {
+    let mut orientations:Vec<(String, Vec<Coord>)> = Vec::new();
+    for polyomino in free_polyominos(piece_size) {
+        for cells in polyomino.orientations() {
+            orientations.push((polyomino.name.to_string(), cells));
+        }
+    }
+    return orientations;
+}
+
+/// Search for a division of the filled cells into pentominos, optionally
+/// requiring distinct classes, sums that are multiples of `SUM_MODULUS` and
+/// the classes given by pentomino clues.
+struct DivisionSearch<'a> {
+    digits:&'a Digits,
+    orientations:&'a [(String, Vec<Coord>)],
+    /// First filled cell seen from each pentomino clue, with the clue's class
+    firsts:&'a [(Coord, &'a str)],
+    distinct:bool,
+    sums:bool,
+    clues:bool,
+    covered:Vec<Vec<bool>>,
+    pieces:Division,
+    /// First uncovered cell of the largest partial division that could not
+    /// be extended
+    stuck:Option<(usize, Coord)>
+}
+impl DivisionSearch<'_> {
+    fn search(&mut self) -> bool {
+        let dim = self.digits.len();
+        let anchor = match cells(dim).find(|c| digit_at(self.digits, c).is_some() && !self.covered[c.y as usize][c.x as usize]) {
+            Some(anchor) => anchor,
+            None => return true
+        };
+        // The anchor is the first uncovered cell, so it is the first cell of
+        // the pentomino covering it
+        for (class, shape) in self.orientations.iter() {
+            if self.distinct && self.pieces.iter().any(|(c, _)| c == class) {
+                continue;
+            }
+            let offset = Coord::new(anchor.x - shape[0].x, anchor.y - shape[0].y);
+            let placed:Vec<Coord> = shape.iter().map(|c| c + &offset).collect();
+            if !placed.iter().all(|c| c.x >= 0 && c.y >= 0 && (c.x as usize) < dim && (c.y as usize) < dim && digit_at(self.digits, c).is_some() && !self.covered[c.y as usize][c.x as usize]) {
+                continue;
+            }
+            let sum:usize = placed.iter().map(|c| digit_at(self.digits, c).unwrap()).sum();
+            if self.sums && !sum.is_multiple_of(SUM_MODULUS) {
+                continue;
+            }
+            if self.clues && self.firsts.iter().any(|(first, required)| required != class && placed.contains(first)) {
+                continue;
+            }
+            for c in placed.iter() {
+                self.covered[c.y as usize][c.x as usize] = true;
+            }
+            self.pieces.push((class.clone(), placed));
+            if self.search() {
+                return true;
+            }
+            let (_, placed) = self.pieces.pop().unwrap();
+            for c in placed.iter() {
+                self.covered[c.y as usize][c.x as usize] = false;
+            }
+        }
+        if self.stuck.is_none_or(|(pieces, _)| self.pieces.len() > pieces) {
+            self.stuck = Some((self.pieces.len(), anchor));
+        }
+        return false;
+    }
+}
+
+/// Return a division of the filled cells into pentominos, or the cell left
+/// uncovered by the largest partial division if there is none.
+fn divide(digits:&Digits, orientations:&[(String, Vec<Coord>)], firsts:&[(Coord, &str)], (distinct, sums, clues):(bool, bool, bool)) -> Result<Division, Coord> {
+    let dim = digits.len();
+    let mut division = DivisionSearch{digits:digits, orientations:orientations, firsts:firsts, distinct:distinct, sums:sums, clues:clues, covered:vec![vec![false; dim]; dim], pieces:Vec::new(), stuck:None};
+    if division.search() {
+        return Ok(division.pieces);
+    }
+    return Err(division.stuck.unwrap().1);
+}
+
+/// Check the division of the filled cells into distinct pieces of
+/// `piece_size` cells with sums that are multiples of `SUM_MODULUS`, matching
+/// the pentomino clues. If there is no such division, the rules broken by the
+/// closest division found are reported instead, the pentomino clues being
+/// dropped first, then the sums, then distinctness.
+fn pentomino_violations(digits:&Digits, puzzle:&Puzzle) -> (Option<Division>, Vec<Violation>) {
+    let orientations = pentomino_orientations(puzzle.piece_size);
+    let noun = piece_noun(puzzle.piece_size);
+    let firsts:Vec<(Coord, &str)> = puzzle.clues.iter().filter_map(|clue| match &clue.value {
+        ClueValue::Pentomino(class) => clue.cells(puzzle.dim).into_iter().find(|c| digit_at(digits, c).is_some()).map(|first| (first, class.as_str())),
+        _ => None
+    }).collect();
+    if let Ok(pieces) = divide(digits, &orientations, &firsts, (true, true, true)) {
+        return (Some(pieces), Vec::new());
+    }
+    let mut closest = divide(digits, &orientations, &firsts, (true, true, false));
+    for rules in [(true, false, true), (true, false, false), (false, true, true), (false, true, false), (false, false, true), (false, false, false)] {
+        if closest.is_ok() {
+            break;
+        }
+        closest = divide(digits, &orientations, &firsts, rules);
+    }
+    let pieces = match closest {
+        Ok(pieces) => pieces,
+        Err(stuck) => {
+            let filled = regions(digits, true).iter().map(|r| r.len()).sum::<usize>();
+            return (None, vec![Violation{rule:"pentominos", message:format!("the {} filled cells cannot be divided into {}s; every division leaves the cell at {} uncovered", filled, noun, stuck)}]);
+        }
+    };
+    let mut violations = vec![Violation{rule:"pentominos", message:format!("no division of the filled cells into distinct {}s with sums that are multiples of {} matching the {} clues exists; the closest division found breaks the rules below", noun, SUM_MODULUS, noun)}];
+    for (class, cells) in pieces.iter() {
+        let uses:Vec<&Vec<Coord>> = pieces.iter().filter(|(c, _)| c == class).map(|(_, cells)| cells).collect();
+        if uses.len() > 1 && std::ptr::eq(uses[0], cells) {
+            let places:Vec<String> = uses.iter().map(|cells| coord_list(cells)).collect();
+            violations.push(Violation{rule:"pentominos", message:format!("the {} {} is used {} times, at {}", class, noun, uses.len(), places.join("; "))});
+        }
+    }
+    for (class, cells) in pieces.iter() {
+        let sum:usize = cells.iter().map(|c| digit_at(digits, c).unwrap()).sum();
+        if !sum.is_multiple_of(SUM_MODULUS) {
+            violations.push(Violation{rule:"pentomino sums", message:format!("the {} {} at {} sums to {}, which is not a multiple of {}", class, noun, coord_list(cells), sum, SUM_MODULUS)});
+        }
+    }
+    return (Some(pieces), violations);
+}
+
+/// Check a proposed solution against every rule and clue of a puzzle,
+/// independently of the search: the hook partition (against `labels` if a
+/// hook layout is given, or else the layout fitting the digits best), the
+/// digits in each hook, the digit counts, the 2x2 rule, connectivity, the
+/// division into distinct pentominos with sums that are multiples of
+/// `SUM_MODULUS`, the given digits and the edge clues.
+pub fn verify_grid(puzzle:&Puzzle, digits:&Digits, labels:Option<&[Vec<usize>]>) -> GridReport {
+    let dim = puzzle.dim;
+    let mut violations:Vec<Violation> = Vec::new();
+    for (coord, d) in puzzle.givens.iter() {
+        match digit_at(digits, coord) {
+            Some(found) if found == *d => {},
+            Some(found) => violations.push(Violation{rule:"givens", message:format!("cell {} is given as {} but holds {}", coord, d, found)}),
+            None => violations.push(Violation{rule:"givens", message:format!("cell {} is given as {} but is empty", coord, d)})
+        }
+    }
+    for d in 1..=dim {
+        let found:Vec<Coord> = cells(dim).filter(|c| digit_at(digits, c) == Some(d)).collect();
+        if found.len() != d {
+            violations.push(Violation{rule:"digit counts", message:format!("digit {} appears {} times, expected {}", d, found.len(), d)});
+        }
+    }
+    let hooks = match labels {
+        Some(labels) => {
+            let (hooks, layout_violations) = layout_hooks(labels, dim);
+            if layout_violations.is_empty() {
+                violations.extend(hook_digit_violations(&hooks, digits));
+            }
+            violations.extend(layout_violations);
+            hooks
+        },
+        None => {
+            let (hooks, layout_violations) = best_layout(digits);
+            if !layout_violations.is_empty() {
+                violations.push(Violation{rule:"hooks", message:"no hook layout fits the digits; the closest layout found breaks the rules below".to_string()});
+            }
+            violations.extend(layout_violations);
+            hooks
+        }
+    };
+    for y in 0..dim.saturating_sub(1) {
+        for x in 0..dim - 1 {
+            if [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)].iter().all(|(x, y)| digits[*y][*x].is_some()) {
+                violations.push(Violation{rule:"2x2", message:format!("the 2x2 block with upper-left cell ({},{}) is completely filled", x, y)});
+            }
+        }
+    }
+    let mut filled_regions = regions(digits, true);
+    if filled_regions.len() > 1 {
+        filled_regions.sort_by_key(|r| std::cmp::Reverse(r.len()));
+        for region in filled_regions[1..].iter() {
+            violations.push(Violation{rule:"connectivity", message:format!("filled cells {} are not connected to the largest filled region, which contains {}", coord_list(region), filled_regions[0][0])});
+        }
+    }
+    let (pentominos, pentomino_violations) = pentomino_violations(digits, puzzle);
+    violations.extend(pentomino_violations);
+    for clue in puzzle.clues.iter() {
+        let line:Vec<usize> = clue.cells(dim).iter().filter_map(|c| digit_at(digits, c)).collect();
+        let found = match clue.value {
+            ClueValue::Sum(_) => Some(line.iter().sum::<usize>()),
+            ClueValue::Product(_) => Some(line.iter().product::<usize>()),
+            ClueValue::Gcd(_) => Some(line.iter().copied().fold(0, gcd)),
+            _ => None
+        };
+        match (&clue.value, found) {
+            (ClueValue::Sum(n) | ClueValue::Product(n) | ClueValue::Gcd(n), Some(found)) if found != *n => {
+                let message = if line.is_empty() {
+                    format!("{}, but the line has no filled cell", clue)
+                }
+                else {
+                    format!("{}, but the filled cells {} give {}", clue, line.iter().map(|d| d.to_string()).collect::<Vec<String>>().join(" "), found)
+                };
+                violations.push(Violation{rule:"edge clues", message:message});
+            },
+            _ => {}
+        }
+        if !clue.value.is_first_seen() {
+            continue;
+        }
+        let first = clue.cells(dim).into_iter().find(|c| digit_at(digits, c).is_some());
+        let first = match first {
+            Some(first) => first,
+            None => {
+                violations.push(Violation{rule:"edge clues", message:format!("{}, but the line has no filled cell", clue)});
+                continue;
+            }
+        };
+        match &clue.value {
+            ClueValue::Digit(d) => {
+                let found = digit_at(digits, &first).unwrap();
+                if found != *d {
+                    violations.push(Violation{rule:"edge clues", message:format!("{}, but the first filled cell {} holds {}", clue, first, found)});
+                }
+            },
+            ClueValue::Pentomino(class) => match pentominos.as_ref().and_then(|pieces| pieces.iter().find(|(_, cells)| cells.contains(&first))) {
+                Some((found, _)) if found == class => {},
+                Some((found, _)) => violations.push(Violation{rule:"edge clues", message:format!("{}, but the first filled cell {} is in the {} {}", clue, first, found, piece_noun(puzzle.piece_size))}),
+                None => violations.push(Violation{rule:"edge clues", message:format!("{} cannot be checked without a division into {}s", clue, piece_noun(puzzle.piece_size))})
+            },
+            ClueValue::Sum(_) | ClueValue::Product(_) | ClueValue::Gcd(_) => {}
+        }
+    }
+    let answer = regions(digits, false).iter().map(|r| r.len()).product();
+    return GridReport{hooks:hooks, pentominos:pentominos, violations:violations, answer:answer};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_grid, verify_grid};
+    use crate::puzzle::Puzzle;
+
+    /// A grid with two divisions into distinct pentominos with valid sums,
+    /// T V Z and I U V, which differ on the piece holding the top right cell
+    const GRID:&str = "....5\n..335\n4.4.5\n4.235\n412.5";
+
+    /// Return the pieces of the division chosen with a clue on the right of
+    /// the first row, and the rules it breaks.
+    fn check(clue:&str) -> (Vec<String>, Vec<&'static str>) {
+        let border = ". . . . . . .";
+        let open = ". . . . . . .";
+        let puzzle = Puzzle::parse(&format!("{}\n. . . . . . {}\n{}\n{}\n{}\n{}\n{}\n", border, clue, open, open, open, open, border)).unwrap();
+        let digits = parse_grid(GRID, puzzle.dim).unwrap();
+        let report = verify_grid(&puzzle, &digits, None);
+        let mut pieces:Vec<String> = report.pentominos.unwrap().into_iter().map(|(class, _)| class).collect();
+        pieces.sort();
+        return (pieces, report.violations.iter().map(|v| v.rule).collect());
+    }
+
+    #[test]
+    fn division_follows_the_pentomino_clues() {
+        assert_eq!(check("Z"), (vec!["T".to_string(), "V".to_string(), "Z".to_string()], vec![]));
+        assert_eq!(check("I"), (vec!["I".to_string(), "U".to_string(), "V".to_string()], vec![]));
+        let (_, rules) = check("F");
+        assert!(rules.contains(&"pentominos") && rules.contains(&"edge clues"));
+    }
+}